@@ -0,0 +1,131 @@
+//! The machine-readable description of a compilation printed by `--build-plan`.
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::emit::{Emit, OutputPaths};
+use crate::manifest::{Target, TargetKind};
+
+#[derive(Debug, Serialize)]
+pub struct BuildPlan {
+  pub compiler: String,
+  pub units: Vec<Unit>,
+}
+
+/// One input file of `flc check`, or one target of `flc build`, and
+/// everything compiling it reads and writes.
+#[derive(Debug, Serialize)]
+pub struct Unit {
+  pub inputs: Vec<PathBuf>,
+  pub phases: Vec<&'static str>,
+  pub outputs: Vec<Output>,
+  /// Other units that have to be compiled first, by index into `units`.
+  pub dependencies: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Output {
+  pub kind: String,
+  /// `None` if the artifact goes to stdout.
+  pub path: Option<PathBuf>,
+}
+
+impl BuildPlan {
+  pub fn new(inputs: &[PathBuf], emits: &[Emit], paths: &OutputPaths) -> Self {
+    let units = inputs
+      .iter()
+      .map(|input| Unit::new(input, emits, paths))
+      .collect();
+
+    Self {
+      compiler: format!("flc {}", env!("CARGO_PKG_VERSION")),
+      units,
+    }
+  }
+
+  /// The plan of `flc build`, its units in the order they are built.
+  pub fn for_targets(units: Vec<Unit>) -> Self {
+    Self {
+      compiler: format!("flc {}", env!("CARGO_PKG_VERSION")),
+      units,
+    }
+  }
+}
+
+impl Unit {
+  fn new(input: &Path, emits: &[Emit], paths: &OutputPaths) -> Self {
+    let mut phases = vec!["read", "lex", "parse"];
+    if !emits.is_empty() {
+      phases.push("emit");
+    }
+
+    let outputs = emits
+      .iter()
+      .map(|emit| Output {
+        kind: emit.kind.to_string(),
+        path: emit.output_path(input, paths, emits.len()),
+      })
+      .collect();
+
+    // Files don't reference each other yet, so every unit stands alone.
+    Self {
+      inputs: vec![input.to_path_buf()],
+      phases,
+      outputs,
+      dependencies: Vec::new(),
+    }
+  }
+
+  /// Building `target` from `inputs` into `output`. A binary is linked
+  /// against the libraries of its `dependencies`.
+  pub fn for_target(
+    target: &Target,
+    inputs: Vec<PathBuf>,
+    output: PathBuf,
+    dependencies: Vec<usize>,
+  ) -> Self {
+    let (kind, phases) = match target.kind {
+      TargetKind::Lib => ("library", vec!["read", "lex", "parse", "emit"]),
+      TargetKind::Bin => ("binary", vec!["read", "lex", "parse", "link", "emit"]),
+    };
+
+    Self {
+      inputs,
+      phases,
+      outputs: vec![Output {
+        kind: kind.to_string(),
+        path: Some(output),
+      }],
+      dependencies,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn plan_to_json() {
+    let emits = ["tokens".parse().unwrap()];
+    let paths = OutputPaths {
+      output: None,
+      out_dir: Some(PathBuf::from("build")),
+    };
+    let plan = BuildPlan::new(&[PathBuf::from("src/main.fl")], &emits, &paths);
+
+    assert_eq!(
+      serde_json::to_value(&plan).unwrap(),
+      serde_json::json!({
+        "compiler": format!("flc {}", env!("CARGO_PKG_VERSION")),
+        "units": [{
+          "inputs": ["src/main.fl"],
+          "phases": ["read", "lex", "parse", "emit"],
+          "outputs": [{ "kind": "tokens", "path": "build/main.tokens" }],
+          "dependencies": [],
+        }],
+      })
+    );
+  }
+}
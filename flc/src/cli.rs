@@ -0,0 +1,224 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+use clap_complete::Shell;
+
+use crate::emit::{Emit, EmitParser, OutputPaths};
+use crate::manifest::OptLevel;
+use crate::scaffold::Template;
+
+/// The fadelang compiler.
+///
+/// Global options can also be given in the `FADE_FLAGS` environment variable,
+/// and `-O` and `--target` in `fade.toml`; the command line takes precedence
+/// over `FADE_FLAGS`, which takes precedence over `fade.toml`.
+#[derive(Debug, Parser)]
+#[command(name = "flc", version)]
+pub struct Cli {
+  #[command(subcommand)]
+  pub command: Command,
+
+  #[command(flatten)]
+  pub global: GlobalArgs,
+}
+
+#[derive(Debug, Default, Args)]
+pub struct GlobalArgs {
+  /// Optimization level, from 0 to 3
+  #[arg(short = 'O', long, global = true, value_name = "LEVEL")]
+  pub opt_level: Option<OptLevel>,
+
+  /// The target to compile for
+  #[arg(long, global = true)]
+  pub target: Option<String>,
+
+  /// Print the time spent in each compiler phase
+  #[arg(long, global = true)]
+  pub time_passes: bool,
+
+  /// Number of files to process in parallel [default: number of CPUs]
+  #[arg(short, long, global = true, value_name = "N")]
+  pub jobs: Option<usize>,
+
+  /// Write the compiler's tracing spans to PATH, as folded stacks for flamegraphs
+  #[arg(long, global = true, value_name = "PATH")]
+  pub trace_output: Option<PathBuf>,
+
+  /// Don't write a reproduction bundle when the compiler crashes
+  #[arg(long, global = true)]
+  pub no_ice_bundle: bool,
+
+  /// Turn the first compile error into an internal compiler error
+  #[arg(long, global = true, hide = true)]
+  pub treat_err_as_bug: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Create a project in a new directory
+  New {
+    /// The directory to create
+    path: PathBuf,
+
+    #[command(flatten)]
+    args: ScaffoldArgs,
+  },
+  /// Create a project in an existing directory, keeping the files in it
+  Init {
+    /// The directory to create the project in
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    #[command(flatten)]
+    args: ScaffoldArgs,
+  },
+  /// Run the front-end over source files and report errors
+  Check(CheckArgs),
+  /// Build the library and binaries of the project in the current directory
+  Build(BuildArgs),
+  /// Print the token stream of source files
+  Tokenize(InputArgs),
+  /// Print the syntax tree of source files
+  Parse(InputArgs),
+  /// Shrink a source file that crashes the compiler to a minimal reproduction
+  Minimize {
+    /// The crashing source file
+    input: PathBuf,
+
+    /// Write the minimized source here instead of to stdout
+    #[arg(short = 'o', value_name = "PATH")]
+    output: Option<PathBuf>,
+  },
+  /// Demangle symbol names of fadelang functions
+  Demangle {
+    /// The symbols to demangle [default: demangle every symbol in stdin,
+    /// keeping the rest]
+    symbols: Vec<String>,
+  },
+  /// Print a completion script for the given shell
+  Completions {
+    #[arg(value_enum)]
+    shell: Shell,
+  },
+  /// Export editor grammars for fadelang
+  Grammar {
+    #[command(subcommand)]
+    format: GrammarFormat,
+  },
+  /// Inspect the effective configuration
+  Config {
+    #[command(subcommand)]
+    command: ConfigCommand,
+  },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GrammarFormat {
+  /// Write a TextMate grammar and a VS Code language configuration
+  Textmate {
+    /// Directory to write the files to
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    out_dir: PathBuf,
+  },
+  /// Print the grammar in W3C EBNF notation
+  Ebnf,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+  /// Print every option's effective value and where it was set
+  Show,
+}
+
+#[derive(Debug, Args)]
+pub struct BuildArgs {
+  /// Build the library [default: every target, if neither `--lib` nor
+  /// `--bin` is given]
+  #[arg(long)]
+  pub lib: bool,
+
+  /// Build the binary with this name, can be repeated
+  #[arg(long, value_name = "NAME")]
+  pub bin: Vec<String>,
+
+  /// Write the artifacts into this directory [default: `out` next to
+  /// `fade.toml`]
+  #[arg(long, value_name = "DIR")]
+  pub out_dir: Option<PathBuf>,
+
+  /// Print the build plan as JSON instead of running it
+  #[arg(long)]
+  pub build_plan: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct ScaffoldArgs {
+  /// Create a program with a `main` function [default]
+  #[arg(long, conflicts_with = "lib")]
+  pub bin: bool,
+
+  /// Create a library for other packages to depend on
+  #[arg(long)]
+  pub lib: bool,
+
+  /// The package name [default: the directory name]
+  #[arg(long)]
+  pub name: Option<String>,
+}
+
+impl ScaffoldArgs {
+  pub fn template(&self) -> Template {
+    if self.lib {
+      Template::Lib
+    } else {
+      Template::Bin
+    }
+  }
+}
+
+#[derive(Debug, Args)]
+pub struct InputArgs {
+  /// The source files to process [default: the sources of the project in
+  /// the current directory, see `fade.toml`]
+  pub inputs: Vec<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct CheckArgs {
+  #[command(flatten)]
+  pub input: InputArgs,
+
+  /// Write intermediate artifacts, as `<kind>[=<path>]` with `-` for stdout
+  #[arg(long, value_name = "KIND[=PATH]", value_delimiter = ',', value_parser = EmitParser)]
+  pub emit: Vec<Emit>,
+
+  /// Write the emitted artifact to this path
+  #[arg(
+    short = 'o',
+    value_name = "PATH",
+    requires = "emit",
+    conflicts_with = "out_dir"
+  )]
+  pub output: Option<PathBuf>,
+
+  /// Write emitted artifacts into this directory
+  #[arg(long, value_name = "DIR", requires = "emit")]
+  pub out_dir: Option<PathBuf>,
+
+  /// Check again every time one of the inputs changes
+  #[arg(long, conflicts_with = "build_plan")]
+  pub watch: bool,
+
+  /// Print the build plan as JSON instead of running it
+  #[arg(long)]
+  pub build_plan: bool,
+}
+
+impl CheckArgs {
+  pub fn output_paths(&self) -> OutputPaths {
+    OutputPaths {
+      output: self.output.clone(),
+      out_dir: self.out_dir.clone(),
+    }
+  }
+}
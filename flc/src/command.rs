@@ -0,0 +1,647 @@
+use std::fmt::{Display, Formatter};
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use clap::CommandFactory;
+use clap_complete::Shell;
+use fll::api::{Artifact, Diagnostic, Diagnostics, Session};
+use fll::ast::Module;
+use fll::mangle::{self, Symbol};
+use rayon::prelude::*;
+
+use crate::build_plan::{BuildPlan, Unit};
+use crate::cli::{BuildArgs, CheckArgs, Cli, GlobalArgs, InputArgs, ScaffoldArgs};
+use crate::config::{Config, FLAGS_ENV};
+use crate::emit::{self, Emit, EmitKind, EmitTarget, OutputPaths};
+use crate::exit_code;
+use crate::grammar;
+use crate::link::{self, Binary, Library};
+use crate::manifest::{Project, Target, TargetKind, MANIFEST_FILE};
+use crate::minimize::{self, CrashOracle};
+use crate::scaffold;
+use crate::timing::PassTimer;
+
+#[derive(Debug)]
+pub struct CommandError {
+  message: String,
+  exit_code: i32,
+}
+
+impl Display for CommandError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.message)
+  }
+}
+
+impl CommandError {
+  fn new(message: String) -> Self {
+    Self {
+      message,
+      exit_code: exit_code::COMPILE_ERROR,
+    }
+  }
+
+  fn usage(message: String) -> Self {
+    Self {
+      message,
+      exit_code: exit_code::INVALID_INVOCATION,
+    }
+  }
+
+  pub fn exit_code(&self) -> i32 {
+    self.exit_code
+  }
+
+  fn diagnostics(diagnostics: Diagnostics) -> Self {
+    let messages: Vec<_> = diagnostics.iter().map(Diagnostic::message).collect();
+    Self::new(messages.join("\nerror: "))
+  }
+
+  fn io(path: &Path, err: std::io::Error) -> Self {
+    Self::new(format!("{}: {}", path.display(), err))
+  }
+}
+
+pub type CommandResult = Result<(), CommandError>;
+
+/// The project around the current directory, if any.
+fn discover_project() -> Result<(PathBuf, Option<Project>), CommandError> {
+  let cwd = std::env::current_dir().map_err(|err| CommandError::io(Path::new("."), err))?;
+  let project = Project::discover(&cwd).map_err(|err| CommandError::new(err.to_string()))?;
+  Ok((cwd, project))
+}
+
+/// Like `resolve_config`, with the manifest of the project around the
+/// current directory as well. Only the commands compiling something read
+/// it, so a broken manifest doesn't get in the way of e.g. `flc new`.
+pub fn resolve_project_config(args: &GlobalArgs) -> Result<Config, CommandError> {
+  let (_, project) = discover_project()?;
+  resolve_config(args, project.as_ref())
+}
+
+/// Layers the global options over `FADE_FLAGS` and the manifest of
+/// `project`, if given.
+pub fn resolve_config(
+  args: &GlobalArgs,
+  project: Option<&Project>,
+) -> Result<Config, CommandError> {
+  let env_flags = std::env::var(FLAGS_ENV).ok();
+
+  Config::resolve(
+    args,
+    env_flags.as_deref(),
+    project.as_ref().map(|project| &project.manifest),
+  )
+  .map_err(|err| {
+    // Only keep clap's first line, its usage hints refer to the command line.
+    let message = err.to_string();
+    let message = message.lines().next().unwrap_or_default();
+    CommandError::usage(format!(
+      "invalid `{}`: {}",
+      FLAGS_ENV,
+      message.trim_start_matches("error: ")
+    ))
+  })
+}
+
+/// A file to work on and the session to compile it with.
+pub struct Input {
+  pub path: PathBuf,
+  pub session: Arc<Session>,
+}
+
+/// The files to work on: the ones given on the command line, or else every
+/// source file of the project around the current directory and of the
+/// packages it depends on. Files from a package are compiled with the
+/// configuration of that package, the others with `config`.
+pub fn resolve_inputs(args: &InputArgs, config: &Config) -> Result<Vec<Input>, CommandError> {
+  if !args.inputs.is_empty() {
+    let session = Arc::new(config.session());
+    return Ok(
+      args
+        .inputs
+        .iter()
+        .map(|path| Input {
+          path: path.clone(),
+          session: session.clone(),
+        })
+        .collect(),
+    );
+  }
+
+  let (cwd, project) = discover_project()?;
+  match project {
+    Some(project) => {
+      // Dependencies come first so their errors are reported before the
+      // errors they might cause in the packages using them.
+      let mut packages = project
+        .resolve_dependencies()
+        .map_err(|err| CommandError::new(err.to_string()))?;
+      packages.push(project);
+
+      let mut inputs = Vec::new();
+      for package in packages {
+        let session = Arc::new(config.for_package(&package.manifest).session());
+        inputs.extend(
+          package
+            .source_files()
+            .map_err(|err| CommandError::new(err.to_string()))?
+            .into_iter()
+            .map(|path| Input {
+              path,
+              session: session.clone(),
+            }),
+        );
+      }
+      Ok(inputs)
+    }
+    None => Err(CommandError::usage(format!(
+      "no input files given and no `{}` found in `{}` or its parents",
+      MANIFEST_FILE,
+      cwd.display()
+    ))),
+  }
+}
+
+fn write_emit(
+  emit: &Emit,
+  input: &Path,
+  paths: &OutputPaths,
+  emit_count: usize,
+  artifact: &Artifact,
+  stdout: &mut Vec<u8>,
+) -> CommandResult {
+  let write = |out: &mut dyn std::io::Write| match emit.kind {
+    EmitKind::Tokens => emit::write_tokens(out, artifact.tokens()),
+    EmitKind::Ast => emit::write_ast(out, artifact.module()),
+  };
+
+  match emit.output_path(input, paths, emit_count) {
+    Some(path) => {
+      if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+      {
+        fs::create_dir_all(parent).map_err(|err| CommandError::io(parent, err))?;
+      }
+
+      let file = File::create(&path).map_err(|err| CommandError::io(&path, err))?;
+      write(&mut BufWriter::new(file)).map_err(|err| CommandError::io(&path, err))
+    }
+    None => write(stdout).map_err(|err| CommandError::io(input, err)),
+  }
+}
+
+/// Runs `f` over all inputs in parallel. A failing input doesn't stop the
+/// others. What `f` writes to its buffer is printed to stdout and failures are
+/// reported in input order, so the output doesn't depend on scheduling.
+fn for_each_input(
+  verb: &str,
+  inputs: &[Input],
+  f: impl Fn(&Path, &Session, &mut Vec<u8>) -> CommandResult + Sync,
+) -> CommandResult {
+  let results: Vec<_> = inputs
+    .par_iter()
+    .map(|input| {
+      let mut stdout = Vec::new();
+      let result = f(&input.path, &input.session, &mut stdout);
+      (stdout, result)
+    })
+    .collect();
+
+  let mut failed = 0;
+
+  for (stdout, result) in results {
+    std::io::stdout()
+      .write_all(&stdout)
+      .map_err(|err| CommandError::io(Path::new("<stdout>"), err))?;
+
+    if let Err(err) = result {
+      eprintln!("error: {}", err);
+      failed += 1;
+    }
+  }
+
+  if failed == 0 {
+    Ok(())
+  } else {
+    Err(CommandError::new(format!(
+      "could not {} {} of {} files",
+      verb,
+      failed,
+      inputs.len()
+    )))
+  }
+}
+
+pub fn check(args: &CheckArgs, config: &Config, timer: &PassTimer) -> CommandResult {
+  let inputs = resolve_inputs(&args.input, config)?;
+
+  if inputs.len() > 1 {
+    if args.output.is_some() {
+      return Err(CommandError::usage(
+        "cannot use `-o` when checking multiple files, use `--out-dir` instead".to_string(),
+      ));
+    }
+
+    if let Some(emit) = args
+      .emit
+      .iter()
+      .find(|emit| matches!(emit.target, EmitTarget::Path(_)))
+    {
+      return Err(CommandError::usage(format!(
+        "cannot write `{}` to a single path when checking multiple files",
+        emit.kind
+      )));
+    }
+  }
+
+  let paths = args.output_paths();
+
+  if args.build_plan {
+    let sources: Vec<_> = inputs.iter().map(|input| input.path.clone()).collect();
+    let plan = BuildPlan::new(&sources, &args.emit, &paths);
+    let json = serde_json::to_string_pretty(&plan)
+      .map_err(|err| CommandError::new(format!("could not serialize build plan: {}", err)))?;
+
+    println!("{}", json);
+    return Ok(());
+  }
+
+  for_each_input("check", &inputs, |input, session, stdout| {
+    let artifact = session.compile(input).map_err(CommandError::diagnostics)?;
+
+    timer.time("emit", input, || {
+      args
+        .emit
+        .iter()
+        .try_for_each(|emit| write_emit(emit, input, &paths, args.emit.len(), &artifact, stdout))
+    })
+  })
+}
+
+pub fn build(args: &BuildArgs, config: &Config) -> CommandResult {
+  let (cwd, project) = discover_project()?;
+  let project = project.ok_or_else(|| {
+    CommandError::usage(format!(
+      "no `{}` found in `{}` or its parents",
+      MANIFEST_FILE,
+      cwd.display()
+    ))
+  })?;
+  let package = &project.manifest.package.name;
+  let targets = project.targets();
+
+  if args.lib && !targets.iter().any(|target| target.kind == TargetKind::Lib) {
+    return Err(CommandError::usage(format!(
+      "package `{}` has no library, add a `src/lib.fl` or a `[lib]` table",
+      package
+    )));
+  }
+  if let Some(name) = args.bin.iter().find(|name| {
+    !targets
+      .iter()
+      .any(|target| target.kind == TargetKind::Bin && &target.name == *name)
+  }) {
+    return Err(CommandError::usage(format!(
+      "package `{}` has no binary named `{}`",
+      package, name
+    )));
+  }
+
+  let build_all = !args.lib && args.bin.is_empty();
+  let selected: Vec<_> = targets
+    .iter()
+    .filter(|target| match target.kind {
+      TargetKind::Lib => build_all || args.lib,
+      TargetKind::Bin => build_all || args.bin.contains(&target.name),
+    })
+    .collect();
+  if selected.is_empty() {
+    return Err(CommandError::usage(format!(
+      "package `{}` has nothing to build, add a `src/main.fl` or a `src/lib.fl`",
+      package
+    )));
+  }
+
+  let out_dir = args
+    .out_dir
+    .clone()
+    .unwrap_or_else(|| project.root.join("out"));
+
+  // Binaries link against the libraries of the packages they depend on and
+  // of their own package, so those are built first.
+  let has_bin = selected.iter().any(|target| target.kind == TargetKind::Bin);
+  let dependencies = if has_bin {
+    project
+      .resolve_dependencies()
+      .map_err(|err| CommandError::new(err.to_string()))?
+  } else {
+    Vec::new()
+  };
+  let has_lib = targets.iter().any(|target| target.kind == TargetKind::Lib);
+  let builds_own_lib =
+    has_lib && (has_bin || selected.iter().any(|target| target.kind == TargetKind::Lib));
+  let bins: Vec<_> = selected
+    .into_iter()
+    .filter(|target| target.kind == TargetKind::Bin)
+    .collect();
+
+  if args.build_plan {
+    let mut units = Vec::new();
+    let packages = dependencies
+      .iter()
+      .chain(builds_own_lib.then_some(&project));
+    for package in packages {
+      let target = library_target(package)?;
+      units.push(Unit::for_target(
+        &target,
+        target_sources(package, &target)?,
+        artifact_path(&out_dir, &target),
+        Vec::new(),
+      ));
+    }
+    let libraries: Vec<_> = (0..units.len()).collect();
+    for target in bins {
+      units.push(Unit::for_target(
+        target,
+        target_sources(&project, target)?,
+        artifact_path(&out_dir, target),
+        libraries.clone(),
+      ));
+    }
+
+    let json = serde_json::to_string_pretty(&BuildPlan::for_targets(units))
+      .map_err(|err| CommandError::new(format!("could not serialize build plan: {}", err)))?;
+    println!("{}", json);
+    return Ok(());
+  }
+
+  fs::create_dir_all(&out_dir).map_err(|err| CommandError::io(&out_dir, err))?;
+  let mut libraries = Vec::new();
+  for dependency in &dependencies {
+    let session = config.for_package(&dependency.manifest).session();
+    libraries.push(build_library(dependency, &session, &out_dir)?);
+  }
+
+  // The package's own library comes first in link order, as it's the one
+  // calling into the others.
+  let session = config.session();
+  if builds_own_lib {
+    libraries.insert(0, build_library(&project, &session, &out_dir)?);
+  }
+
+  for target in bins {
+    let modules = compile_modules(&target_sources(&project, target)?, &session)?;
+    let binary = Binary::link(&target.name, &modules, &libraries).map_err(CommandError::new)?;
+
+    write_json(&artifact_path(&out_dir, target), &binary)?;
+    println!("built binary `{}`", target.name);
+  }
+  Ok(())
+}
+
+fn library_target(project: &Project) -> Result<Target, CommandError> {
+  project
+    .targets()
+    .into_iter()
+    .find(|target| target.kind == TargetKind::Lib)
+    .ok_or_else(|| {
+      CommandError::new(format!(
+        "package `{}` has no library to link against",
+        project.manifest.package.name
+      ))
+    })
+}
+
+/// Where the artifact of `target` is written.
+fn artifact_path(out_dir: &Path, target: &Target) -> PathBuf {
+  let extension = match target.kind {
+    TargetKind::Lib => link::LIBRARY_EXTENSION,
+    TargetKind::Bin => link::BINARY_EXTENSION,
+  };
+  out_dir.join(format!("{}.{}", target.name, extension))
+}
+
+fn build_library(
+  project: &Project,
+  session: &Session,
+  out_dir: &Path,
+) -> Result<Library, CommandError> {
+  let package = &project.manifest.package;
+  let target = library_target(project)?;
+
+  let modules = compile_modules(&target_sources(project, &target)?, session)?;
+  let library = Library::new(&package.name, &package.version, &modules);
+
+  write_json(&artifact_path(out_dir, &target), &library)?;
+  println!("built library `{}`", package.name);
+  Ok(library)
+}
+
+/// The files `target` is built from: its root, and the package's sources
+/// except the roots of other binaries. A binary of a package with a library
+/// links against it instead, so it's built from its root alone.
+fn target_sources(project: &Project, target: &Target) -> Result<Vec<PathBuf>, CommandError> {
+  let targets = project.targets();
+  if target.kind == TargetKind::Bin && targets.iter().any(|other| other.kind == TargetKind::Lib) {
+    return Ok(vec![target.root.clone()]);
+  }
+
+  let other_bins: Vec<_> = targets
+    .into_iter()
+    .filter(|other| other.kind == TargetKind::Bin && other.root != target.root)
+    .map(|other| other.root)
+    .collect();
+
+  let mut sources = project
+    .source_files()
+    .map_err(|err| CommandError::new(err.to_string()))?;
+  sources.retain(|source| !other_bins.contains(source));
+  if !sources.contains(&target.root) {
+    sources.push(target.root.clone());
+  }
+  Ok(sources)
+}
+
+fn compile_modules(sources: &[PathBuf], session: &Session) -> Result<Vec<Module>, CommandError> {
+  sources
+    .iter()
+    .map(|source| {
+      session
+        .compile(source.as_path())
+        .map(|artifact| artifact.module().clone())
+        .map_err(CommandError::diagnostics)
+    })
+    .collect()
+}
+
+fn write_json(path: &Path, value: &impl serde::Serialize) -> CommandResult {
+  let json = serde_json::to_string_pretty(value).map_err(|err| {
+    CommandError::new(format!("could not serialize `{}`: {}", path.display(), err))
+  })?;
+  fs::write(path, json + "\n").map_err(|err| CommandError::io(path, err))
+}
+
+pub fn tokenize(args: &InputArgs, config: &Config) -> CommandResult {
+  for_each_input(
+    "tokenize",
+    &resolve_inputs(args, config)?,
+    |input, session, stdout| {
+      let tokens = session.tokenize(input).map_err(CommandError::diagnostics)?;
+
+      emit::write_tokens(stdout, &tokens).map_err(|err| CommandError::io(input, err))
+    },
+  )
+}
+
+pub fn parse(args: &InputArgs, config: &Config) -> CommandResult {
+  for_each_input(
+    "parse",
+    &resolve_inputs(args, config)?,
+    |input, session, stdout| {
+      let artifact = session.compile(input).map_err(CommandError::diagnostics)?;
+
+      emit::write_ast(stdout, artifact.module()).map_err(|err| CommandError::io(input, err))
+    },
+  )
+}
+
+pub fn new(path: &Path, args: &ScaffoldArgs) -> CommandResult {
+  if path.exists() {
+    return Err(CommandError::usage(format!(
+      "`{}` already exists, use `flc init` to create a project in it",
+      path.display()
+    )));
+  }
+  create_project(path, args)
+}
+
+pub fn init(path: &Path, args: &ScaffoldArgs) -> CommandResult {
+  let manifest = path.join(MANIFEST_FILE);
+  if manifest.exists() {
+    return Err(CommandError::usage(format!(
+      "`{}` already exists",
+      manifest.display()
+    )));
+  }
+  create_project(path, args)
+}
+
+/// Writes the skeleton of the chosen template into `root`, skipping the
+/// files that are already there.
+fn create_project(root: &Path, args: &ScaffoldArgs) -> CommandResult {
+  let name = match &args.name {
+    Some(name) => name.clone(),
+    None => fs::canonicalize(root)
+      .or_else(|_| std::path::absolute(root))
+      .map_err(|err| CommandError::io(root, err))?
+      .file_name()
+      .map(|name| name.to_string_lossy().into_owned())
+      .ok_or_else(|| {
+        CommandError::usage(format!(
+          "`{}` has no directory name to name the package after, use `--name`",
+          root.display()
+        ))
+      })?,
+  };
+  scaffold::check_name(&name).map_err(|err| {
+    CommandError::usage(format!(
+      "package {}; use `--name` to choose another one",
+      err
+    ))
+  })?;
+
+  let template = args.template();
+  for (file, contents) in scaffold::files(&name, template) {
+    let path = root.join(file);
+    if path.exists() {
+      continue;
+    }
+    if let Some(dir) = path.parent() {
+      fs::create_dir_all(dir).map_err(|err| CommandError::io(dir, err))?;
+    }
+    fs::write(&path, contents).map_err(|err| CommandError::io(&path, err))?;
+  }
+
+  println!("created {} package `{}`", template, name);
+  Ok(())
+}
+
+pub fn demangle(symbols: &[String]) -> CommandResult {
+  if !symbols.is_empty() {
+    for symbol in symbols {
+      let demangled = Symbol::demangle(symbol).map_or_else(|| symbol.clone(), |s| s.to_string());
+      println!("{}", demangled);
+    }
+    return Ok(());
+  }
+
+  let stdin = Path::new("<stdin>");
+  let mut stdout = std::io::stdout().lock();
+  for line in std::io::stdin().lines() {
+    let line = line.map_err(|err| CommandError::io(stdin, err))?;
+    writeln!(stdout, "{}", mangle::demangle_all(&line))
+      .map_err(|err| CommandError::io(Path::new("<stdout>"), err))?;
+  }
+  Ok(())
+}
+
+pub fn completions(shell: Shell) -> CommandResult {
+  clap_complete::generate(shell, &mut Cli::command(), "flc", &mut std::io::stdout());
+  Ok(())
+}
+
+pub fn config_show(config: &Config) -> CommandResult {
+  print!("{}", config.show());
+  Ok(())
+}
+
+pub fn grammar_textmate(out_dir: &Path) -> CommandResult {
+  fs::create_dir_all(out_dir).map_err(|err| CommandError::io(out_dir, err))?;
+
+  for (file, value) in [
+    (grammar::TEXTMATE_GRAMMAR_FILE, grammar::textmate_grammar()),
+    (
+      grammar::LANGUAGE_CONFIGURATION_FILE,
+      grammar::language_configuration(),
+    ),
+  ] {
+    let path = out_dir.join(file);
+    let json = serde_json::to_string_pretty(&value)
+      .map_err(|err| CommandError::new(format!("could not serialize `{}`: {}", file, err)))?;
+
+    fs::write(&path, json + "\n").map_err(|err| CommandError::io(&path, err))?;
+  }
+  Ok(())
+}
+
+pub fn grammar_ebnf() -> CommandResult {
+  print!("{}", grammar::ebnf());
+  Ok(())
+}
+
+pub fn minimize(input: &Path, output: Option<&Path>, config: &Config) -> CommandResult {
+  let source = fs::read_to_string(input).map_err(|err| CommandError::io(input, err))?;
+  let oracle = CrashOracle::new(config.treat_err_as_bug.value)
+    .map_err(|err| CommandError::io(Path::new("flc"), err))?;
+
+  let minimized = minimize::minimize(&source, &oracle)
+    .map_err(|err| CommandError::io(oracle.scratch(), err))?
+    .ok_or_else(|| {
+      CommandError::new(format!(
+        "{}: checking this file does not crash the compiler",
+        input.display()
+      ))
+    })?;
+
+  match output {
+    Some(path) => fs::write(path, minimized).map_err(|err| CommandError::io(path, err)),
+    None => {
+      print!("{}", minimized);
+      Ok(())
+    }
+  }
+}
@@ -0,0 +1,339 @@
+//! Resolution of the options that can be set in more than one place.
+//!
+//! Every option is taken from the first of these that sets it:
+//!
+//! 1. the command line,
+//! 2. the `FADE_FLAGS` environment variable, holding whitespace-separated
+//!    global flags as they would be written on the command line,
+//! 3. the `[package]` table of `fade.toml`, for `opt-level`, `target` and
+//!    `automatic-semicolons`, the last one from the manifest of the package
+//!    being compiled,
+//! 4. the built-in default.
+
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use clap::Parser;
+use fll::api::Session;
+
+use crate::cli::GlobalArgs;
+use crate::manifest::{Manifest, OptLevel};
+use crate::timing::PassTimer;
+
+pub const FLAGS_ENV: &str = "FADE_FLAGS";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConfigSource {
+  Default,
+  Manifest,
+  Environment,
+  CommandLine,
+}
+
+impl Display for ConfigSource {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ConfigSource::Default => write!(f, "default"),
+      ConfigSource::Manifest => write!(f, "fade.toml"),
+      ConfigSource::Environment => write!(f, "{}", FLAGS_ENV),
+      ConfigSource::CommandLine => write!(f, "command line"),
+    }
+  }
+}
+
+/// A resolved option and where its value came from.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Setting<T> {
+  pub value: T,
+  pub source: ConfigSource,
+}
+
+impl<T> Setting<T> {
+  fn resolve(layers: [(Option<T>, ConfigSource); 3], default: T) -> Self {
+    layers
+      .into_iter()
+      .find_map(|(value, source)| value.map(|value| Self { value, source }))
+      .unwrap_or(Self {
+        value: default,
+        source: ConfigSource::Default,
+      })
+  }
+}
+
+/// The effective configuration of an flc invocation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+  pub opt_level: Setting<OptLevel>,
+  pub target: Setting<Option<String>>,
+  pub jobs: Setting<Option<usize>>,
+  pub time_passes: Setting<bool>,
+  pub trace_output: Setting<Option<PathBuf>>,
+  pub ice_bundle: Setting<bool>,
+  pub treat_err_as_bug: Setting<bool>,
+  pub automatic_semicolons: Setting<bool>,
+}
+
+/// Only manifests set it, as it's a property of the sources rather than of
+/// the invocation.
+fn automatic_semicolons(manifest: Option<&Manifest>) -> Setting<bool> {
+  Setting::resolve(
+    [
+      (None, ConfigSource::CommandLine),
+      (None, ConfigSource::Environment),
+      (
+        manifest.and_then(|manifest| manifest.package.automatic_semicolons),
+        ConfigSource::Manifest,
+      ),
+    ],
+    false,
+  )
+}
+
+/// Parses the contents of `FADE_FLAGS` with the same rules as the command line.
+#[derive(Debug, Parser)]
+#[command(name = FLAGS_ENV, no_binary_name = true)]
+struct EnvFlags {
+  #[command(flatten)]
+  global: GlobalArgs,
+}
+
+impl Config {
+  pub fn resolve(
+    cli: &GlobalArgs,
+    env_flags: Option<&str>,
+    manifest: Option<&Manifest>,
+  ) -> Result<Self, clap::Error> {
+    let env = match env_flags {
+      Some(flags) => EnvFlags::try_parse_from(flags.split_whitespace())?.global,
+      None => GlobalArgs::default(),
+    };
+    let package = manifest.map(|manifest| &manifest.package);
+
+    // Plain flags can only switch something one way, so they count as unset
+    // unless given.
+    Ok(Self {
+      opt_level: Setting::resolve(
+        [
+          (cli.opt_level, ConfigSource::CommandLine),
+          (env.opt_level, ConfigSource::Environment),
+          (package.and_then(|p| p.opt_level), ConfigSource::Manifest),
+        ],
+        OptLevel::default(),
+      ),
+      target: Setting::resolve(
+        [
+          (cli.target.clone().map(Some), ConfigSource::CommandLine),
+          (env.target.map(Some), ConfigSource::Environment),
+          (
+            package.and_then(|p| p.target.clone()).map(Some),
+            ConfigSource::Manifest,
+          ),
+        ],
+        None,
+      ),
+      jobs: Setting::resolve(
+        [
+          (cli.jobs.map(Some), ConfigSource::CommandLine),
+          (env.jobs.map(Some), ConfigSource::Environment),
+          (None, ConfigSource::Manifest),
+        ],
+        None,
+      ),
+      time_passes: Setting::resolve(
+        [
+          (cli.time_passes.then_some(true), ConfigSource::CommandLine),
+          (env.time_passes.then_some(true), ConfigSource::Environment),
+          (None, ConfigSource::Manifest),
+        ],
+        false,
+      ),
+      trace_output: Setting::resolve(
+        [
+          (
+            cli.trace_output.clone().map(Some),
+            ConfigSource::CommandLine,
+          ),
+          (env.trace_output.map(Some), ConfigSource::Environment),
+          (None, ConfigSource::Manifest),
+        ],
+        None,
+      ),
+      ice_bundle: Setting::resolve(
+        [
+          (
+            cli.no_ice_bundle.then_some(false),
+            ConfigSource::CommandLine,
+          ),
+          (
+            env.no_ice_bundle.then_some(false),
+            ConfigSource::Environment,
+          ),
+          (None, ConfigSource::Manifest),
+        ],
+        true,
+      ),
+      treat_err_as_bug: Setting::resolve(
+        [
+          (
+            cli.treat_err_as_bug.then_some(true),
+            ConfigSource::CommandLine,
+          ),
+          (
+            env.treat_err_as_bug.then_some(true),
+            ConfigSource::Environment,
+          ),
+          (None, ConfigSource::Manifest),
+        ],
+        false,
+      ),
+      automatic_semicolons: automatic_semicolons(manifest),
+    })
+  }
+
+  /// The configuration to compile the package of `manifest` with. How its
+  /// sources are written is up to the package, so `automatic-semicolons`
+  /// comes from its own manifest; the other options are the invocation's.
+  pub fn for_package(&self, manifest: &Manifest) -> Self {
+    Self {
+      automatic_semicolons: automatic_semicolons(Some(manifest)),
+      ..self.clone()
+    }
+  }
+
+  /// A compiler session configured with the resolved options, timing its
+  /// phases with a `PassTimer`.
+  pub fn session(&self) -> Session {
+    let builder = Session::builder()
+      .observer(Arc::new(PassTimer::new(self.time_passes.value)))
+      .opt_level(self.opt_level.value.level())
+      .treat_err_as_bug(self.treat_err_as_bug.value)
+      .automatic_semicolons(self.automatic_semicolons.value);
+
+    match &self.target.value {
+      Some(target) => builder.target(target).build(),
+      None => builder.build(),
+    }
+  }
+
+  /// Renders the configuration for `flc config show`.
+  pub fn show(&self) -> String {
+    let target = match &self.target.value {
+      Some(target) => format!("\"{}\"", target),
+      None => "(host)".to_string(),
+    };
+    let trace_output = match &self.trace_output.value {
+      Some(path) => format!("\"{}\"", path.display()),
+      None => "(none)".to_string(),
+    };
+    let jobs = match self.jobs.value {
+      Some(jobs) => jobs.to_string(),
+      None => "(number of CPUs)".to_string(),
+    };
+
+    [
+      (
+        "opt-level",
+        self.opt_level.value.to_string(),
+        self.opt_level.source,
+      ),
+      ("target", target, self.target.source),
+      ("jobs", jobs, self.jobs.source),
+      (
+        "time-passes",
+        self.time_passes.value.to_string(),
+        self.time_passes.source,
+      ),
+      ("trace-output", trace_output, self.trace_output.source),
+      (
+        "ice-bundle",
+        self.ice_bundle.value.to_string(),
+        self.ice_bundle.source,
+      ),
+      (
+        "automatic-semicolons",
+        self.automatic_semicolons.value.to_string(),
+        self.automatic_semicolons.source,
+      ),
+    ]
+    .iter()
+    .map(|(name, value, source)| format!("{} = {}  # {}\n", name, value, source))
+    .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn manifest() -> Manifest {
+    toml::from_str(
+      r#"
+      [package]
+      name = "hello"
+      version = "0.1.0"
+      target = "x86_64-linux"
+      opt-level = 2
+      automatic-semicolons = true
+      "#,
+    )
+    .unwrap()
+  }
+
+  #[test]
+  fn defaults() {
+    let config = Config::resolve(&GlobalArgs::default(), None, None).unwrap();
+
+    assert_eq!(config.opt_level.value, OptLevel::default());
+    assert_eq!(config.opt_level.source, ConfigSource::Default);
+    assert_eq!(config.target.value, None);
+    assert!(!config.time_passes.value);
+    assert!(config.ice_bundle.value);
+    assert!(!config.automatic_semicolons.value);
+  }
+
+  #[test]
+  fn precedence() {
+    let manifest = manifest();
+    let cli = GlobalArgs {
+      jobs: Some(2),
+      ..GlobalArgs::default()
+    };
+    let config = Config::resolve(
+      &cli,
+      Some("-O 3 --jobs 8 --time-passes --no-ice-bundle"),
+      Some(&manifest),
+    )
+    .unwrap();
+
+    assert_eq!(config.opt_level.value, "3".parse().unwrap());
+    assert_eq!(config.opt_level.source, ConfigSource::Environment);
+    assert_eq!(config.target.value.as_deref(), Some("x86_64-linux"));
+    assert_eq!(config.target.source, ConfigSource::Manifest);
+    assert_eq!(config.jobs.value, Some(2));
+    assert_eq!(config.jobs.source, ConfigSource::CommandLine);
+    assert!(config.time_passes.value);
+    assert!(!config.ice_bundle.value);
+    assert!(config.automatic_semicolons.value);
+    assert_eq!(config.automatic_semicolons.source, ConfigSource::Manifest);
+    assert!(config.session().automatic_semicolons());
+  }
+
+  #[test]
+  fn package_config() {
+    let config = Config::resolve(&GlobalArgs::default(), Some("-O 2"), None).unwrap();
+    let package = config.for_package(&manifest());
+
+    assert!(!config.automatic_semicolons.value);
+    assert!(package.automatic_semicolons.value);
+    assert_eq!(package.automatic_semicolons.source, ConfigSource::Manifest);
+    assert_eq!(package.opt_level, config.opt_level);
+    assert_eq!(package.target.value, None);
+  }
+
+  #[test]
+  fn invalid_env_flags() {
+    assert!(Config::resolve(&GlobalArgs::default(), Some("--bogus"), None).is_err());
+    assert!(Config::resolve(&GlobalArgs::default(), Some("-O 9"), None).is_err());
+  }
+}
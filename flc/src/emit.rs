@@ -0,0 +1,250 @@
+use std::ffi::OsStr;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use clap::builder::{PossibleValue, TypedValueParser};
+use fll::api::{Span, Token};
+use fll::ast::Module;
+
+/// An intermediate artifact that can be written out with `--emit`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EmitKind {
+  Tokens,
+  Ast,
+}
+
+impl EmitKind {
+  pub const ALL: [EmitKind; 2] = [EmitKind::Tokens, EmitKind::Ast];
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      EmitKind::Tokens => "tokens",
+      EmitKind::Ast => "ast",
+    }
+  }
+
+  pub fn extension(&self) -> &'static str {
+    match self {
+      EmitKind::Tokens => "tokens",
+      EmitKind::Ast => "ast",
+    }
+  }
+}
+
+impl Display for EmitKind {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// Where an emitted artifact ends up.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EmitTarget {
+  /// In the current directory, named after the input file.
+  Default,
+  Stdout,
+  Path(PathBuf),
+}
+
+/// A single `--emit=<kind>[=<path>]` request.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Emit {
+  pub kind: EmitKind,
+  pub target: EmitTarget,
+}
+
+/// Where `-o` and `--out-dir` asked for artifacts to be written.
+#[derive(Clone, Debug, Default)]
+pub struct OutputPaths {
+  /// Names the artifact outright if only one kind is emitted, otherwise
+  /// serves as the stem every kind's extension is appended to.
+  pub output: Option<PathBuf>,
+  /// Directory for artifacts that are named after their input file.
+  pub out_dir: Option<PathBuf>,
+}
+
+impl Emit {
+  /// Resolves the file an artifact for `input` is written to, `None` meaning stdout.
+  ///
+  /// An explicit `<kind>=<path>` always wins over `-o` and `--out-dir`.
+  pub fn output_path(
+    &self,
+    input: &Path,
+    paths: &OutputPaths,
+    emit_count: usize,
+  ) -> Option<PathBuf> {
+    match &self.target {
+      EmitTarget::Default => match &paths.output {
+        Some(output) if emit_count == 1 => Some(output.clone()),
+        Some(output) => Some(output.with_extension(self.kind.extension())),
+        None => {
+          let stem = input.file_stem().unwrap_or(input.as_os_str());
+          let file = PathBuf::from(stem).with_extension(self.kind.extension());
+
+          match &paths.out_dir {
+            Some(out_dir) => Some(out_dir.join(file)),
+            None => Some(file),
+          }
+        }
+      },
+      EmitTarget::Stdout => None,
+      EmitTarget::Path(path) => Some(path.clone()),
+    }
+  }
+}
+
+impl FromStr for Emit {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (kind, target) = match s.split_once('=') {
+      Some((kind, "-")) => (kind, EmitTarget::Stdout),
+      Some((kind, path)) => (kind, EmitTarget::Path(PathBuf::from(path))),
+      None => (s, EmitTarget::Default),
+    };
+
+    let kind = EmitKind::ALL
+      .into_iter()
+      .find(|k| k.name() == kind)
+      .ok_or_else(|| {
+        let expected: Vec<_> = EmitKind::ALL.iter().map(EmitKind::name).collect();
+        format!(
+          "unknown emit kind `{}`, expected one of: {}",
+          kind,
+          expected.join(", ")
+        )
+      })?;
+
+    Ok(Self { kind, target })
+  }
+}
+
+/// Parses `--emit` values while still advertising the known kinds, so they
+/// show up in `--help` and in generated shell completions.
+#[derive(Clone, Copy, Debug)]
+pub struct EmitParser;
+
+impl TypedValueParser for EmitParser {
+  type Value = Emit;
+
+  fn parse_ref(
+    &self,
+    cmd: &clap::Command,
+    arg: Option<&clap::Arg>,
+    value: &OsStr,
+  ) -> Result<Self::Value, clap::Error> {
+    (|value: &str| value.parse::<Emit>()).parse_ref(cmd, arg, value)
+  }
+
+  fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+    Some(Box::new(
+      EmitKind::ALL
+        .iter()
+        .map(|kind| PossibleValue::new(kind.name())),
+    ))
+  }
+}
+
+pub fn write_tokens(out: &mut dyn Write, tokens: &[(Token, Span)]) -> std::io::Result<()> {
+  for (token, _) in tokens {
+    writeln!(out, "{:?}", token)?;
+  }
+  Ok(())
+}
+
+pub fn write_ast(out: &mut dyn Write, module: &Module) -> std::io::Result<()> {
+  writeln!(out, "{:#?}", module)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_emit() {
+    assert_eq!(
+      "tokens".parse(),
+      Ok(Emit {
+        kind: EmitKind::Tokens,
+        target: EmitTarget::Default,
+      })
+    );
+    assert_eq!(
+      "tokens=-".parse(),
+      Ok(Emit {
+        kind: EmitKind::Tokens,
+        target: EmitTarget::Stdout,
+      })
+    );
+    assert_eq!(
+      "tokens=out/add.tok".parse(),
+      Ok(Emit {
+        kind: EmitKind::Tokens,
+        target: EmitTarget::Path(PathBuf::from("out/add.tok")),
+      })
+    );
+    assert_eq!(
+      "ast".parse(),
+      Ok(Emit {
+        kind: EmitKind::Ast,
+        target: EmitTarget::Default,
+      })
+    );
+    assert!("asm".parse::<Emit>().is_err());
+  }
+
+  #[test]
+  fn default_output_path() {
+    let emit = Emit {
+      kind: EmitKind::Tokens,
+      target: EmitTarget::Default,
+    };
+    let input = Path::new("src/add.fl");
+
+    assert_eq!(
+      emit.output_path(input, &OutputPaths::default(), 1),
+      Some(PathBuf::from("add.tokens"))
+    );
+
+    let out_dir = OutputPaths {
+      output: None,
+      out_dir: Some(PathBuf::from("build")),
+    };
+    assert_eq!(
+      emit.output_path(input, &out_dir, 1),
+      Some(PathBuf::from("build/add.tokens"))
+    );
+  }
+
+  #[test]
+  fn explicit_output_path() {
+    let default = Emit {
+      kind: EmitKind::Tokens,
+      target: EmitTarget::Default,
+    };
+    let explicit = Emit {
+      kind: EmitKind::Tokens,
+      target: EmitTarget::Path(PathBuf::from("add.tok")),
+    };
+    let input = Path::new("src/add.fl");
+    let paths = OutputPaths {
+      output: Some(PathBuf::from("out/add")),
+      out_dir: None,
+    };
+
+    assert_eq!(
+      default.output_path(input, &paths, 1),
+      Some(PathBuf::from("out/add"))
+    );
+    assert_eq!(
+      default.output_path(input, &paths, 2),
+      Some(PathBuf::from("out/add.tokens"))
+    );
+    assert_eq!(
+      explicit.output_path(input, &paths, 1),
+      Some(PathBuf::from("add.tok"))
+    );
+  }
+}
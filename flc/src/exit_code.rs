@@ -0,0 +1,13 @@
+//! The exit codes `flc` guarantees to its callers.
+
+/// Everything succeeded.
+pub const SUCCESS: i32 = 0;
+
+/// The input could not be compiled, e.g. it has errors or could not be read.
+pub const COMPILE_ERROR: i32 = 1;
+
+/// The command line was invalid. clap exits with the same code on its own errors.
+pub const INVALID_INVOCATION: i32 = 2;
+
+/// The compiler itself failed, see `ice`.
+pub const INTERNAL_COMPILER_ERROR: i32 = 101;
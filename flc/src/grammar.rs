@@ -0,0 +1,382 @@
+//! Railroad-diagram and editor grammars, generated from the rules in
+//! `fll::grammar`.
+
+use fll::grammar::{Expression, Grammar};
+use fll::token::OperatorType;
+use serde_json::{json, Value};
+
+pub const LANGUAGE: &str = "fadelang";
+pub const FILE_EXTENSION: &str = "fl";
+
+pub const TEXTMATE_GRAMMAR_FILE: &str = "fadelang.tmLanguage.json";
+pub const LANGUAGE_CONFIGURATION_FILE: &str = "language-configuration.json";
+
+fn terminals(expression: &Expression) -> Vec<&str> {
+  match expression {
+    Expression::Terminal(text) => vec![text],
+    Expression::Choice(alternatives) => alternatives.iter().flat_map(terminals).collect(),
+    _ => Vec::new(),
+  }
+}
+
+/// The bracket pairs, in the order the `bracket` rule lists them.
+fn bracket_pairs(grammar: &Grammar) -> Vec<[&str; 2]> {
+  terminals(grammar.rule("bracket"))
+    .chunks(2)
+    .map(|pair| [pair[0], pair[1]])
+    .collect()
+}
+
+/// The terminals the rule `name` starts and ends with, like the quotes of
+/// `string`.
+fn delimiters<'a>(grammar: &'a Grammar, name: &str) -> [&'a str; 2] {
+  match grammar.rule(name) {
+    Expression::Sequence(items) => [&items[0], &items[items.len() - 1]]
+      .map(|item| terminals(item).first().copied().unwrap_or_default()),
+    _ => ["", ""],
+  }
+}
+
+/// `expression` as a regular expression. Rules are inlined, and the
+/// exceptions of `Except` are left out: the editor grammar lists the
+/// patterns they stand for first.
+fn regex(grammar: &Grammar, expression: &Expression) -> String {
+  let escape = |c: char, special: &dyn Fn(char) -> bool| {
+    if special(c) {
+      format!("\\{}", c)
+    } else if c.is_ascii_graphic() {
+      c.to_string()
+    } else {
+      format!("\\x{{{:X}}}", c as u32)
+    }
+  };
+  // `_` is a word character, escaping it isn't valid in every flavor.
+  let escape_text = |c| escape(c, &|c: char| c.is_ascii_punctuation() && c != '_');
+  let escape_class = |c| escape(c, &|c| matches!(c, '\\' | '^' | '-' | '[' | ']'));
+  let group = |inner: &Expression| match inner {
+    Expression::Class { .. } | Expression::Rule(_) | Expression::Choice(_) => regex(grammar, inner),
+    Expression::Terminal(text) if text.chars().count() == 1 => regex(grammar, inner),
+    _ => format!("(?:{})", regex(grammar, inner)),
+  };
+
+  match expression {
+    Expression::Terminal(text) => text.chars().map(escape_text).collect(),
+    Expression::Rule(name) => group(grammar.rule(name)),
+    Expression::Class { ranges, negated } => {
+      let ranges: String = ranges
+        .iter()
+        .map(|(low, high)| match low == high {
+          true => escape_class(*low),
+          false => format!("{}-{}", escape_class(*low), escape_class(*high)),
+        })
+        .collect();
+      format!("[{}{}]", if *negated { "^" } else { "" }, ranges)
+    }
+    Expression::Sequence(items) => items.iter().map(|item| regex(grammar, item)).collect(),
+    Expression::Choice(alternatives) => {
+      let alternatives: Vec<_> = alternatives
+        .iter()
+        .map(|alternative| regex(grammar, alternative))
+        .collect();
+      format!("(?:{})", alternatives.join("|"))
+    }
+    Expression::Optional(inner) => format!("{}?", group(inner)),
+    Expression::ZeroOrMore(inner) => format!("{}*", group(inner)),
+    Expression::OneOrMore(inner) => format!("{}+", group(inner)),
+    Expression::Except(inner, _) => regex(grammar, inner),
+  }
+}
+
+/// The `.tmLanguage.json` grammar.
+pub fn textmate_grammar() -> Value {
+  let grammar = Grammar::default();
+  let scope = |name: &str| format!("{}.{}", name, LANGUAGE);
+  let rule = |name: &str| regex(&grammar, grammar.rule(name));
+  let delimiters = |name| {
+    delimiters(&grammar, name)
+      .map(|delimiter| regex(&grammar, &Expression::Terminal(delimiter.to_string())))
+  };
+  let [open, close] = delimiters("string");
+  let [block_open, block_close] = delimiters("block");
+
+  json!({
+    "$schema": "https://raw.githubusercontent.com/martinring/tmlanguage/master/tmlanguage.json",
+    "name": LANGUAGE,
+    "scopeName": format!("source.{}", LANGUAGE),
+    "fileTypes": [FILE_EXTENSION],
+    "patterns": [
+      { "include": "#strings" },
+      { "include": "#numbers" },
+      { "include": "#types" },
+      { "include": "#keywords" },
+      { "include": "#functions" },
+      { "include": "#identifiers" },
+      { "include": "#operators" },
+      { "include": "#blocks" },
+      { "include": "#brackets" },
+    ],
+    "repository": {
+      "strings": {
+        "begin": open,
+        "end": close,
+        "name": scope("string.quoted.double"),
+        "patterns": [{
+          "match": rule("escape"),
+          "name": scope("constant.character.escape"),
+        }],
+      },
+      "numbers": {
+        "match": format!("\\b{}\\b", rule("integer")),
+        "name": scope("constant.numeric"),
+      },
+      // Types follow the `:` of a parameter or the `->` of a return type.
+      "types": {
+        "match": format!(
+          "(?<={}|{})\\s*({})\\b",
+          regex(&grammar, &Expression::Terminal(OperatorType::TypeSpecifier.symbol().to_string())),
+          regex(&grammar, &Expression::Terminal(OperatorType::ReturnType.symbol().to_string())),
+          rule("type")
+        ),
+        "captures": { "1": { "name": scope("storage.type") } },
+      },
+      "keywords": {
+        "match": format!("\\b{}\\b", rule("keyword")),
+        "name": scope("keyword.other"),
+      },
+      "functions": {
+        "match": format!("\\b({})\\s*(?=\\()", rule("identifier")),
+        "captures": { "1": { "name": scope("entity.name.function") } },
+      },
+      "identifiers": {
+        "match": format!("\\b{}\\b", rule("identifier")),
+        "name": scope("variable.other"),
+      },
+      "operators": {
+        "match": rule("operator"),
+        "name": scope("keyword.operator"),
+      },
+      "blocks": {
+        "begin": block_open,
+        "end": block_close,
+        "name": scope("meta.block"),
+        "beginCaptures": { "0": { "name": scope("punctuation.section.block") } },
+        "endCaptures": { "0": { "name": scope("punctuation.section.block") } },
+        "patterns": [{ "include": "$self" }],
+      },
+      "brackets": {
+        "match": rule("bracket"),
+        "name": scope("punctuation.section"),
+      },
+    },
+  })
+}
+
+/// A terminal in W3C EBNF, which has no escapes in quoted strings.
+fn terminal(text: &str) -> String {
+  match text {
+    "\\" => "#x5C".to_string(),
+    text if text.chars().any(|c| !c.is_ascii_graphic()) => text
+      .chars()
+      .map(|c| format!("#x{:X}", c as u32))
+      .collect::<Vec<_>>()
+      .join(" "),
+    text if text.contains('"') => format!("'{}'", text),
+    text => format!("\"{}\"", text),
+  }
+}
+
+/// A character in a W3C EBNF character class.
+fn class_char(c: char) -> String {
+  match c {
+    '\\' | '^' | '-' | ']' | '[' => format!("#x{:X}", c as u32),
+    c if c.is_ascii_graphic() => c.to_string(),
+    c => format!("#x{:X}", c as u32),
+  }
+}
+
+/// `expression` in W3C EBNF. Top-level alternatives go on lines of their
+/// own, nested ones are parenthesized.
+fn ebnf_expression(expression: &Expression, top_level: bool) -> String {
+  let group = |inner: &Expression| match inner {
+    Expression::Sequence(_) | Expression::Choice(_) | Expression::Except(..) => {
+      format!("( {} )", ebnf_expression(inner, false))
+    }
+    _ => ebnf_expression(inner, false),
+  };
+
+  match expression {
+    Expression::Terminal(text) => terminal(text),
+    Expression::Rule(name) => name.to_string(),
+    Expression::Class { ranges, negated } => {
+      let ranges: String = ranges
+        .iter()
+        .map(|(low, high)| match low == high {
+          true => class_char(*low),
+          false => format!("{}-{}", class_char(*low), class_char(*high)),
+        })
+        .collect();
+      format!("[{}{}]", if *negated { "^" } else { "" }, ranges)
+    }
+    // A sequence within a sequence needs no parentheses.
+    Expression::Sequence(items) => items
+      .iter()
+      .map(|item| match item {
+        Expression::Sequence(_) => ebnf_expression(item, false),
+        _ => group(item),
+      })
+      .collect::<Vec<_>>()
+      .join(" "),
+    Expression::Choice(alternatives) => alternatives
+      .iter()
+      .map(|alternative| ebnf_expression(alternative, false))
+      .collect::<Vec<_>>()
+      .join(if top_level { "\n  | " } else { " | " }),
+    Expression::Optional(inner) => format!("{}?", group(inner)),
+    Expression::ZeroOrMore(inner) => format!("{}*", group(inner)),
+    Expression::OneOrMore(inner) => format!("{}+", group(inner)),
+    Expression::Except(inner, except) => format!("{} - {}", group(inner), group(except)),
+  }
+}
+
+/// The grammar in the W3C EBNF notation, which railroad diagram generators
+/// read, starting with the syntax of a module and followed by the tokens.
+pub fn ebnf() -> String {
+  Grammar::default()
+    .rules()
+    .iter()
+    .map(|rule| {
+      format!(
+        "{} ::= {}\n",
+        rule.name,
+        ebnf_expression(&rule.expression, true)
+      )
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// The VS Code `language-configuration.json`. fadelang has no comment syntax
+/// in the lexer yet, so none is declared.
+pub fn language_configuration() -> Value {
+  let grammar = Grammar::default();
+  let pairs = bracket_pairs(&grammar);
+  let [open, close] = delimiters(&grammar, "string");
+  let mut auto_closing: Vec<_> = pairs
+    .iter()
+    .map(|[open, close]| json!({ "open": open, "close": close }))
+    .collect();
+  auto_closing.push(json!({ "open": open, "close": close, "notIn": ["string"] }));
+
+  json!({
+    "brackets": pairs,
+    "autoClosingPairs": auto_closing,
+    "surroundingPairs": pairs,
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use fll::token::KEYWORDS;
+
+  use super::*;
+
+  #[test]
+  fn operators_longest_first() {
+    let grammar = textmate_grammar();
+    let pattern = grammar["repository"]["operators"]["match"]
+      .as_str()
+      .unwrap();
+    let alternatives: Vec<_> = pattern
+      .strip_prefix("(?:")
+      .and_then(|pattern| pattern.strip_suffix(')'))
+      .unwrap()
+      .split('|')
+      .collect();
+
+    assert_eq!(alternatives[0], "\\.\\.\\=");
+    assert!(alternatives.contains(&"\\-\\>"));
+    assert_eq!(
+      alternatives.iter().filter(|a| **a == "\\-\\>").count(),
+      1,
+      "shared symbols are only listed once"
+    );
+  }
+
+  #[test]
+  fn keywords_in_grammar() {
+    let grammar = textmate_grammar();
+    let keywords = grammar["repository"]["keywords"]["match"].as_str().unwrap();
+
+    for keyword in KEYWORDS {
+      assert!(keywords.contains(keyword));
+    }
+  }
+
+  #[test]
+  fn textmate_patterns() {
+    let grammar = textmate_grammar();
+    let pattern = |name: &str| grammar["repository"][name]["match"].as_str().unwrap();
+
+    assert_eq!(
+      pattern("numbers"),
+      "\\b(?:[0-9][0-9_]*|0x_*[0-9a-fA-F][0-9a-fA-F_]*|0o_*[0-7][0-7_]*|0b_*[0-1][0-1_]*)\
+       (?:u8|u16|u32|u64|i8|i16|i32|i64)?\\b"
+    );
+    assert_eq!(pattern("identifiers"), "\\b[a-zA-Z_][a-zA-Z0-9_]*\\b");
+    assert_eq!(pattern("brackets"), "(?:\\(|\\)|\\[|\\]|\\{|\\})");
+    assert_eq!(grammar["repository"]["strings"]["begin"], "\\\"");
+    assert_eq!(grammar["repository"]["blocks"]["begin"], "\\{");
+    assert_eq!(grammar["repository"]["blocks"]["end"], "\\}");
+    assert!(pattern("types").starts_with("(?<=\\:|\\-\\>)\\s*((?:u8|u16|"));
+    assert_eq!(
+      grammar["repository"]["strings"]["patterns"][0]["match"],
+      "\\\\(?:\\\"|0|\\\\|n|r|t)"
+    );
+  }
+
+  #[test]
+  fn ebnf_rules() {
+    let grammar = ebnf();
+
+    assert!(grammar.starts_with("module ::= function*\n"));
+    assert!(grammar.contains(
+      "function ::= identifier \"(\" parameters? \")\" ( \":\" \"->\" type )? block? \";\"\n"
+    ));
+    assert!(grammar.contains("statement ::= ( \"return\" expression? | expression ) \";\"\n"));
+    assert!(grammar.contains("or ::= and ( \"||\" and )*\n"));
+    assert!(grammar.contains("sum ::= product ( ( \"+\" | \"-\" ) product )*\n"));
+    assert!(grammar.contains("token ::= keyword\n  | identifier\n"));
+    assert!(grammar.contains("keyword ::= \"u8\"\n  | \"u16\"\n"));
+    assert!(grammar.contains("identifier ::= ( [a-zA-Z_] [a-zA-Z0-9_]* ) - keyword\n"));
+    assert!(grammar.contains("operator ::= \"..=\"\n"));
+    assert!(grammar.contains("\n  | \"->\"\n"));
+    assert!(grammar.contains("string ::= '\"' ( [^\"#x5C] | escape )* '\"'\n"));
+    assert!(
+      grammar.contains("integer ::= ( [0-9] [0-9_]* | \"0x\" \"_\"* [0-9a-fA-F] [0-9a-fA-F_]*")
+    );
+    assert!(grammar.contains("escape ::= #x5C ( '\"' | \"0\" | #x5C | \"n\" | \"r\" | \"t\" )\n"));
+    assert!(grammar.contains("whitespace ::= #x20+\n"));
+    assert!(grammar.contains("newline ::= #xA\n"));
+    for keyword in KEYWORDS {
+      assert!(grammar.contains(&format!("\"{}\"", keyword)));
+    }
+  }
+
+  #[test]
+  fn bracket_configuration() {
+    let configuration = language_configuration();
+
+    assert_eq!(
+      configuration["brackets"],
+      json!([["(", ")"], ["[", "]"], ["{", "}"]])
+    );
+    assert_eq!(
+      configuration["autoClosingPairs"][2],
+      json!({ "open": "{", "close": "}" })
+    );
+    assert_eq!(
+      configuration["autoClosingPairs"][3],
+      json!({ "open": "\"", "close": "\"", "notIn": ["string"] })
+    );
+  }
+}
@@ -0,0 +1,131 @@
+//! Reporting of internal compiler errors (ICEs), i.e. panics inside flc.
+
+use std::backtrace::Backtrace;
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::fs;
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Where the bundle of the first panic went. `flc check --watch` keeps running
+/// after a panic, and a bundle per panic would pile up in the temp directory.
+static BUNDLE: OnceLock<PathBuf> = OnceLock::new();
+
+thread_local! {
+  /// The phases running on this thread, the innermost last.
+  static PHASES: RefCell<Vec<(&'static str, PathBuf)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Marks `phase` as running on this thread until the matching `leave_phase`,
+/// so a panic can be attributed to it.
+pub fn enter_phase(phase: &'static str, input: &Path) {
+  PHASES.with(|phases| phases.borrow_mut().push((phase, input.to_path_buf())));
+}
+
+pub fn leave_phase() {
+  PHASES.with(|phases| phases.borrow_mut().pop());
+}
+
+/// Forgets the phases a caught panic left unfinished.
+pub fn clear_phases() {
+  PHASES.with(|phases| phases.borrow_mut().clear());
+}
+
+/// Marks the phase running on this thread until dropped, also when
+/// unwinding.
+pub struct PhaseGuard(());
+
+impl PhaseGuard {
+  pub fn enter(phase: &'static str, input: &Path) -> Self {
+    enter_phase(phase, input);
+    Self(())
+  }
+}
+
+impl Drop for PhaseGuard {
+  fn drop(&mut self) {
+    leave_phase();
+  }
+}
+
+/// Extends the default panic output with a bug-report message naming the
+/// phase that failed. Unless `write_bundle` is false, a reproduction bundle is
+/// written as well for the first panic of the process, see `write_bundle`.
+pub fn install_panic_hook(write_bundle: bool) {
+  let default_hook = panic::take_hook();
+
+  panic::set_hook(Box::new(move |info| {
+    default_hook(info);
+
+    let phase = PHASES.with(|phases| phases.borrow().last().cloned());
+
+    eprintln!();
+    eprintln!("error: internal compiler error: flc panicked");
+    if let Some((phase, input)) = &phase {
+      eprintln!(
+        "note: the panic happened in phase `{}` for `{}`",
+        phase,
+        input.display()
+      );
+    }
+    eprintln!(
+      "note: this is a bug in flc, please report it together with the input that triggered it"
+    );
+    eprintln!("note: flc {}", env!("CARGO_PKG_VERSION"));
+
+    if write_bundle {
+      match BUNDLE.get() {
+        Some(dir) => eprintln!(
+          "note: not writing another reproduction bundle, the first panic's is in `{}`",
+          dir.display()
+        ),
+        None => match self::write_bundle(info, phase.as_ref()) {
+          Ok(dir) => eprintln!(
+            "note: a reproduction bundle was written to `{}`, please attach it to the report",
+            dir.display()
+          ),
+          Err(err) => eprintln!("note: could not write a reproduction bundle: {}", err),
+        },
+      }
+    }
+  }));
+}
+
+/// Writes a directory with everything needed to reproduce the panic: the
+/// compiler version, command line, failing phase, panic message and
+/// backtrace in `report.txt`, and a copy of the input being processed.
+fn write_bundle(
+  info: &PanicHookInfo,
+  phase: Option<&(&'static str, PathBuf)>,
+) -> std::io::Result<PathBuf> {
+  let timestamp = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_secs())
+    .unwrap_or_default();
+  let dir = std::env::temp_dir().join(format!("flc-ice-{}-{}", timestamp, std::process::id()));
+  fs::create_dir_all(&dir)?;
+
+  let mut report = String::new();
+  let _ = writeln!(report, "version: flc {}", env!("CARGO_PKG_VERSION"));
+  let _ = writeln!(
+    report,
+    "command: {}",
+    std::env::args().collect::<Vec<_>>().join(" ")
+  );
+  if let Some((phase, input)) = phase {
+    let _ = writeln!(report, "phase: {}", phase);
+    let _ = writeln!(report, "input: {}", input.display());
+
+    if let Some(name) = input.file_name() {
+      fs::create_dir_all(dir.join("sources"))?;
+      fs::copy(input, dir.join("sources").join(name))?;
+    }
+  }
+  let _ = writeln!(report, "panic: {}", info);
+  let _ = writeln!(report, "\n{}", Backtrace::force_capture());
+
+  fs::write(dir.join("report.txt"), report)?;
+  Ok(BUNDLE.get_or_init(|| dir).clone())
+}
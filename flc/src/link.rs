@@ -0,0 +1,330 @@
+//! The artifacts `flc build` writes for library and binary targets.
+//!
+//! Without a backend there is no machine code yet. A library is written as
+//! its interface, the functions other packages can call, and a binary as the
+//! list of what a linker would combine: its entry point and the library
+//! symbols it calls.
+
+use std::collections::BTreeSet;
+
+use fll::ast::{Expression, Function, Item, Module, Statement, Type};
+use fll::mangle::Symbol;
+use serde::{Deserialize, Serialize};
+
+pub const LIBRARY_EXTENSION: &str = "flib";
+pub const BINARY_EXTENSION: &str = "fbin";
+
+/// The standard library's modules. The functions they declare without a
+/// body are intrinsics: the compiler provides them, so binaries call them
+/// without linking against anything.
+const STD_MODULES: [&str; 6] = [
+  include_str!("../../lib/fadelang/error.fl"),
+  include_str!("../../lib/fadelang/fs.fl"),
+  include_str!("../../lib/fadelang/io.fl"),
+  include_str!("../../lib/fadelang/math.fl"),
+  include_str!("../../lib/fadelang/prelude.fl"),
+  include_str!("../../lib/fadelang/res.fl"),
+];
+
+/// A built library, which other packages link against.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Library {
+  pub name: String,
+  pub version: String,
+  pub exports: Vec<Export>,
+}
+
+/// A function of a library. There is no visibility yet, so every function
+/// is exported.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Export {
+  pub name: String,
+  pub symbol: String,
+  pub parameters: Vec<String>,
+  pub return_type: Option<String>,
+}
+
+/// A built binary.
+#[derive(Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Binary {
+  pub name: String,
+  /// The symbol of `main`.
+  pub entry: String,
+  /// The libraries the binary links against, in link order.
+  pub libraries: Vec<String>,
+  /// The library symbols the binary calls.
+  pub imports: Vec<String>,
+}
+
+impl Library {
+  pub fn new(name: &str, version: &str, modules: &[Module]) -> Self {
+    let exports = functions(modules)
+      .map(|function| Export {
+        name: function.name.clone(),
+        symbol: Symbol::new([name, &function.name]).mangle(),
+        parameters: function
+          .parameters
+          .iter()
+          .map(|parameter| type_name(&parameter.parameter_type).to_string())
+          .collect(),
+        return_type: function
+          .return_type
+          .as_ref()
+          .map(|t| type_name(t).to_string()),
+      })
+      .collect();
+
+    Self {
+      name: name.to_string(),
+      version: version.to_string(),
+      exports,
+    }
+  }
+}
+
+impl Binary {
+  /// Links the binary made of `modules` against `libraries`. Calls of
+  /// functions the binary defines itself and of intrinsics stay local, the
+  /// others are looked up in the libraries. Calls found in none of them, or
+  /// in more than one, are an error.
+  pub fn link(name: &str, modules: &[Module], libraries: &[Library]) -> Result<Self, String> {
+    let main = functions(modules)
+      .find(|function| function.name == "main")
+      .ok_or_else(|| format!("binary `{}` has no `main` function", name))?;
+
+    let local: BTreeSet<_> = functions(modules).map(|function| &function.name).collect();
+    let mut called = BTreeSet::new();
+    for function in functions(modules) {
+      let mut calls = BTreeSet::new();
+      for statement in function.body.iter().flat_map(|body| &body.statements) {
+        match statement {
+          Statement::Return(Some(expression)) | Statement::Expression(expression) => {
+            collect_calls(expression, &mut calls)
+          }
+          Statement::Return(None) => {}
+        }
+      }
+      // A parameter is called as whatever was passed for it.
+      calls.retain(|callee| {
+        !function
+          .parameters
+          .iter()
+          .any(|parameter| &parameter.name == callee)
+      });
+      called.append(&mut calls);
+    }
+
+    let intrinsics = intrinsics();
+    let mut imports = Vec::new();
+    let mut unresolved = Vec::new();
+    for callee in called
+      .into_iter()
+      .filter(|callee| !local.contains(callee) && !intrinsics.contains(callee.as_str()))
+    {
+      let exporters: Vec<_> = libraries
+        .iter()
+        .filter_map(|library| {
+          library
+            .exports
+            .iter()
+            .find(|export| export.name == callee)
+            .map(|export| (library, export))
+        })
+        .collect();
+      match exporters[..] {
+        [] => unresolved.push(format!("`{}`", callee)),
+        [(_, export)] => imports.push(export.symbol.clone()),
+        _ => {
+          let libraries: Vec<_> = exporters
+            .iter()
+            .map(|(library, _)| format!("`{}`", library.name))
+            .collect();
+          return Err(format!(
+            "binary `{}` calls `{}`, which is ambiguous between the libraries {}",
+            name,
+            callee,
+            libraries.join(", ")
+          ));
+        }
+      }
+    }
+    if !unresolved.is_empty() {
+      return Err(format!(
+        "binary `{}` calls functions that are defined nowhere: {}",
+        name,
+        unresolved.join(", ")
+      ));
+    }
+
+    Ok(Self {
+      name: name.to_string(),
+      entry: Symbol::new([name, &main.name]).mangle(),
+      libraries: libraries
+        .iter()
+        .map(|library| library.name.clone())
+        .collect(),
+      imports,
+    })
+  }
+}
+
+/// The names of the functions the standard library declares without a body.
+/// Its modules use syntax the parser doesn't support yet, so the
+/// declarations are picked out line by line: a top-level function
+/// declaration without a body is a single line ending in `;`.
+fn intrinsics() -> BTreeSet<&'static str> {
+  STD_MODULES
+    .iter()
+    .flat_map(|module| module.lines())
+    .filter(|line| line.trim_end().ends_with(';') && !line.contains('{'))
+    .filter_map(|line| {
+      let (name, _) = line.split_once('(')?;
+      let mut chars = name.chars();
+      let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+      is_identifier.then_some(name)
+    })
+    .collect()
+}
+
+fn functions(modules: &[Module]) -> impl Iterator<Item = &Function> {
+  modules
+    .iter()
+    .flat_map(|module| &module.items)
+    .map(|item| match item {
+      Item::Function(function) => function,
+    })
+}
+
+fn type_name(parameter_type: &Type) -> &str {
+  match parameter_type {
+    Type::Primitive(name) | Type::Named(name) => name,
+  }
+}
+
+/// Adds the names of the functions `expression` calls by name.
+fn collect_calls(expression: &Expression, called: &mut BTreeSet<String>) {
+  match expression {
+    Expression::Call { callee, arguments } => {
+      if let Expression::Identifier(name) = callee.as_ref() {
+        called.insert(name.clone());
+      } else {
+        collect_calls(callee, called);
+      }
+      for argument in arguments {
+        collect_calls(argument, called);
+      }
+    }
+    Expression::Unary { operand, .. } => collect_calls(operand, called),
+    Expression::Binary { left, right, .. } => {
+      collect_calls(left, called);
+      collect_calls(right, called);
+    }
+    Expression::Identifier(_)
+    | Expression::String(_)
+    | Expression::Integer { .. }
+    | Expression::Bool(_) => {}
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use fll::api::{compile, CompileOptions, SourceInput};
+
+  use super::*;
+
+  fn module(source: &str) -> Module {
+    compile(
+      SourceInput::text(source.to_string()),
+      CompileOptions::default(),
+    )
+    .unwrap()
+    .module()
+    .clone()
+  }
+
+  fn math() -> Library {
+    Library::new(
+      "math",
+      "0.1.0",
+      &[module("add(a: u8, b: u8): -> u8 {\n  return a + b;\n};\n")],
+    )
+  }
+
+  #[test]
+  fn library_exports() {
+    assert_eq!(
+      math().exports,
+      [Export {
+        name: "add".to_string(),
+        symbol: "_FN4math3addE".to_string(),
+        parameters: vec!["u8".to_string(), "u8".to_string()],
+        return_type: Some("u8".to_string()),
+      }]
+    );
+  }
+
+  #[test]
+  fn link_binary() {
+    let app = module(
+      "main() {\n  println(add(a, twice(b)));\n};\ntwice(x: u8): -> u8 {\n  return x + x;\n};\n",
+    );
+
+    assert_eq!(
+      Binary::link("app", &[app], &[math()]),
+      Ok(Binary {
+        name: "app".to_string(),
+        entry: "_FN3app4mainE".to_string(),
+        libraries: vec!["math".to_string()],
+        imports: vec!["_FN4math3addE".to_string()],
+      })
+    );
+    assert_eq!(
+      Binary::link("lib", &[module("add();\n")], &[]),
+      Err("binary `lib` has no `main` function".to_string())
+    );
+  }
+
+  #[test]
+  fn intrinsics_of_the_standard_library() {
+    let intrinsics = intrinsics();
+
+    for name in ["println", "assert", "assert_eq", "error", "abs"] {
+      assert!(intrinsics.contains(name), "{}", name);
+    }
+    // Declared with a body, or not at the top level.
+    for name in ["stdout", "delete", "comp"] {
+      assert!(!intrinsics.contains(name), "{}", name);
+    }
+  }
+
+  #[test]
+  fn ambiguous_calls() {
+    let app = module("main() {\n  add(a, b);\n};\n");
+    let calc = Library::new(
+      "calc",
+      "0.1.0",
+      &[module("add(a: u8, b: u8): -> u8 {\n  return a + b;\n};\n")],
+    );
+
+    assert_eq!(
+      Binary::link("app", &[app], &[math(), calc]),
+      Err(
+        "binary `app` calls `add`, which is ambiguous between the libraries `math`, `calc`"
+          .to_string()
+      )
+    );
+  }
+
+  #[test]
+  fn unresolved_calls() {
+    let app = module("main() {\n  add(sub(a, b), mul(a));\n};\napply(f: u8) {\n  f();\n};\n");
+
+    assert_eq!(
+      Binary::link("app", &[app], &[math()]),
+      Err("binary `app` calls functions that are defined nowhere: `mul`, `sub`".to_string())
+    );
+  }
+}
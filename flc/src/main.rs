@@ -0,0 +1,109 @@
+use std::panic;
+
+use clap::Parser;
+
+mod build_plan;
+mod cli;
+mod command;
+mod config;
+mod emit;
+mod exit_code;
+mod grammar;
+mod ice;
+mod link;
+mod manifest;
+mod minimize;
+mod scaffold;
+mod timing;
+mod trace;
+mod watch;
+
+use cli::{Cli, Command, ConfigCommand, GlobalArgs, GrammarFormat};
+use config::Config;
+use timing::PassTimer;
+
+/// `config` doesn't include the manifest; the commands that need it resolve
+/// it themselves.
+fn run(command: Command, global: &GlobalArgs, config: &Config) -> i32 {
+  if let Some(jobs) = config.jobs.value {
+    rayon::ThreadPoolBuilder::new()
+      .num_threads(jobs)
+      .build_global()
+      .expect("the global thread pool is only configured once");
+  }
+
+  let timer = PassTimer::new(config.time_passes.value);
+  let project_config = || command::resolve_project_config(global);
+
+  let result = match command {
+    Command::New { path, args } => command::new(&path, &args),
+    Command::Init { path, args } => command::init(&path, &args),
+    Command::Check(args) if args.watch => project_config().and_then(|config| {
+      let paths: Vec<_> = command::resolve_inputs(&args.input, &config)?
+        .into_iter()
+        .map(|input| input.path)
+        .collect();
+      watch::watch(&paths, || command::check(&args, &config, &timer))
+    }),
+    Command::Check(args) => {
+      project_config().and_then(|config| command::check(&args, &config, &timer))
+    }
+    Command::Build(args) => project_config().and_then(|config| command::build(&args, &config)),
+    Command::Tokenize(args) => {
+      project_config().and_then(|config| command::tokenize(&args, &config))
+    }
+    Command::Parse(args) => project_config().and_then(|config| command::parse(&args, &config)),
+    Command::Minimize { input, output } => command::minimize(&input, output.as_deref(), config),
+    Command::Demangle { symbols } => command::demangle(&symbols),
+    Command::Completions { shell } => command::completions(shell),
+    Command::Grammar {
+      format: GrammarFormat::Textmate { out_dir },
+    } => command::grammar_textmate(&out_dir),
+    Command::Grammar {
+      format: GrammarFormat::Ebnf,
+    } => command::grammar_ebnf(),
+    Command::Config {
+      command: ConfigCommand::Show,
+    } => project_config().and_then(|config| command::config_show(&config)),
+  };
+
+  match result {
+    Ok(()) => exit_code::SUCCESS,
+    Err(err) => {
+      eprintln!("error: {}", err);
+      err.exit_code()
+    }
+  }
+}
+
+fn main() {
+  let cli = Cli::parse();
+  let config = match command::resolve_config(&cli.global, None) {
+    Ok(config) => config,
+    Err(err) => {
+      eprintln!("error: {}", err);
+      std::process::exit(err.exit_code());
+    }
+  };
+  ice::install_panic_hook(config.ice_bundle.value);
+
+  let trace_guard = match trace::init(config.trace_output.value.as_deref()) {
+    Ok(guard) => guard,
+    Err(err) => {
+      eprintln!("error: {}", err);
+      std::process::exit(exit_code::INVALID_INVOCATION);
+    }
+  };
+
+  let code = panic::catch_unwind(|| run(cli.command, &cli.global, &config))
+    .unwrap_or(exit_code::INTERNAL_COMPILER_ERROR);
+
+  // `exit` doesn't run destructors, so the trace has to be flushed by hand.
+  if let Some(guard) = trace_guard {
+    if let Err(err) = guard.flush() {
+      eprintln!("error: could not write the trace: {}", err);
+    }
+  }
+
+  std::process::exit(code);
+}
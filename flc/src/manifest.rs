@@ -0,0 +1,572 @@
+//! The `fade.toml` project manifest.
+
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::scaffold;
+
+pub const MANIFEST_FILE: &str = "fade.toml";
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+  pub package: Package,
+
+  pub lib: Option<LibTarget>,
+
+  #[serde(default)]
+  pub bin: Vec<BinTarget>,
+
+  #[serde(default)]
+  pub dependencies: BTreeMap<String, Dependency>,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Package {
+  pub name: String,
+  pub version: String,
+
+  /// Directories searched for `.fl` files, relative to the manifest.
+  #[serde(default = "default_source_roots")]
+  pub source_roots: Vec<PathBuf>,
+
+  pub target: Option<String>,
+
+  pub opt_level: Option<OptLevel>,
+
+  /// Experimental: line breaks end statements where a `;` is missing.
+  pub automatic_semicolons: Option<bool>,
+}
+
+impl Manifest {
+  /// Checks that the package and its binaries have names that can be
+  /// mangled into symbols.
+  fn check_names(&self) -> Result<(), String> {
+    scaffold::check_name(&self.package.name).map_err(|err| format!("package {}", err))?;
+    for bin in &self.bin {
+      scaffold::check_name(&bin.name).map_err(|err| format!("binary {}", err))?;
+    }
+    Ok(())
+  }
+}
+
+fn default_source_roots() -> Vec<PathBuf> {
+  vec![PathBuf::from("src")]
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(try_from = "u8")]
+pub struct OptLevel(u8);
+
+impl TryFrom<u8> for OptLevel {
+  type Error = String;
+
+  fn try_from(level: u8) -> Result<Self, Self::Error> {
+    if level <= 3 {
+      Ok(Self(level))
+    } else {
+      Err(format!(
+        "opt-level must be between 0 and 3, found {}",
+        level
+      ))
+    }
+  }
+}
+
+impl OptLevel {
+  pub fn level(&self) -> u8 {
+    self.0
+  }
+}
+
+impl FromStr for OptLevel {
+  type Err = String;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let level: u8 = s
+      .parse()
+      .map_err(|_| format!("opt-level must be a number between 0 and 3, found `{}`", s))?;
+    Self::try_from(level)
+  }
+}
+
+impl Display for OptLevel {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+
+/// The `[lib]` table. Without one, a package with a `src/lib.fl` is a
+/// library.
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct LibTarget {
+  /// Relative to the manifest.
+  pub path: PathBuf,
+}
+
+/// A `[[bin]]` table. Without any, a package with a `src/main.fl` has a
+/// binary named like the package.
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct BinTarget {
+  pub name: String,
+  /// The file defining `main`, relative to the manifest.
+  pub path: PathBuf,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TargetKind {
+  Lib,
+  Bin,
+}
+
+/// Something `flc build` produces from a package.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Target {
+  pub kind: TargetKind,
+  pub name: String,
+  /// The file the target is declared with, `src/lib.fl` or the file defining
+  /// `main`.
+  pub root: PathBuf,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Dependency {
+  pub path: Option<PathBuf>,
+  pub version: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ManifestError {
+  Io(PathBuf, std::io::Error),
+  Parse(PathBuf, toml::de::Error),
+  /// A package or binary name that isn't an identifier.
+  InvalidName(PathBuf, String),
+  /// A dependency without a `path`; there is no registry to fetch it from.
+  NoPath {
+    package: String,
+    dependency: String,
+  },
+  /// A dependency whose manifest declares a different package name.
+  NameMismatch {
+    dependency: String,
+    found: String,
+  },
+  /// The packages forming a dependency cycle, starting and ending with the same one.
+  Cycle(Vec<String>),
+}
+
+impl Display for ManifestError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      ManifestError::Io(path, err) => write!(f, "{}: {}", path.display(), err),
+      ManifestError::Parse(path, err) => write!(f, "{}: {}", path.display(), err),
+      ManifestError::InvalidName(path, err) => write!(f, "{}: {}", path.display(), err),
+      ManifestError::NoPath {
+        package,
+        dependency,
+      } => write!(
+        f,
+        "dependency `{}` of `{}` has no `path`, only path dependencies are supported",
+        dependency, package
+      ),
+      ManifestError::NameMismatch { dependency, found } => write!(
+        f,
+        "dependency `{}` points to a package named `{}`",
+        dependency, found
+      ),
+      ManifestError::Cycle(chain) => {
+        write!(f, "dependency cycle: {}", chain.join(" -> "))
+      }
+    }
+  }
+}
+
+/// A manifest together with the directory it was found in.
+#[derive(Debug)]
+pub struct Project {
+  pub root: PathBuf,
+  pub manifest: Manifest,
+}
+
+impl Project {
+  pub fn load(manifest_path: &Path) -> Result<Self, ManifestError> {
+    let text = fs::read_to_string(manifest_path)
+      .map_err(|err| ManifestError::Io(manifest_path.to_path_buf(), err))?;
+    let manifest: Manifest = toml::from_str(&text)
+      .map_err(|err| ManifestError::Parse(manifest_path.to_path_buf(), err))?;
+    manifest
+      .check_names()
+      .map_err(|err| ManifestError::InvalidName(manifest_path.to_path_buf(), err))?;
+    let root = manifest_path
+      .parent()
+      .map(Path::to_path_buf)
+      .unwrap_or_default();
+
+    Ok(Self { root, manifest })
+  }
+
+  /// Looks for a manifest in `dir` and its ancestors.
+  pub fn discover(dir: &Path) -> Result<Option<Self>, ManifestError> {
+    dir
+      .ancestors()
+      .map(|dir| dir.join(MANIFEST_FILE))
+      .find(|path| path.is_file())
+      .map(|path| Self::load(&path))
+      .transpose()
+  }
+
+  /// All packages this one depends on, directly or not, ordered so that every
+  /// package comes after its own dependencies.
+  pub fn resolve_dependencies(&self) -> Result<Vec<Project>, ManifestError> {
+    let mut resolved = Vec::new();
+    let mut chain = vec![self.manifest.package.name.clone()];
+
+    self.visit_dependencies(&mut chain, &mut resolved)?;
+    Ok(resolved)
+  }
+
+  fn visit_dependencies(
+    &self,
+    chain: &mut Vec<String>,
+    resolved: &mut Vec<Project>,
+  ) -> Result<(), ManifestError> {
+    for (name, dependency) in &self.manifest.dependencies {
+      let path = dependency
+        .path
+        .as_ref()
+        .ok_or_else(|| ManifestError::NoPath {
+          package: self.manifest.package.name.clone(),
+          dependency: name.clone(),
+        })?;
+
+      if chain.contains(name) {
+        let start = chain.iter().position(|package| package == name).unwrap();
+        let mut cycle = chain[start..].to_vec();
+        cycle.push(name.clone());
+        return Err(ManifestError::Cycle(cycle));
+      }
+
+      if resolved
+        .iter()
+        .any(|project| &project.manifest.package.name == name)
+      {
+        continue;
+      }
+
+      let project = Project::load(&self.root.join(path).join(MANIFEST_FILE))?;
+      if &project.manifest.package.name != name {
+        return Err(ManifestError::NameMismatch {
+          dependency: name.clone(),
+          found: project.manifest.package.name,
+        });
+      }
+
+      chain.push(name.clone());
+      project.visit_dependencies(chain, resolved)?;
+      chain.pop();
+
+      resolved.push(project);
+    }
+
+    Ok(())
+  }
+
+  /// The library, if any, followed by the binaries.
+  pub fn targets(&self) -> Vec<Target> {
+    let name = &self.manifest.package.name;
+    let lib = match &self.manifest.lib {
+      Some(lib) => Some(self.root.join(&lib.path)),
+      None => Some(self.root.join("src/lib.fl")).filter(|path| path.is_file()),
+    };
+    let bins: Vec<_> = if self.manifest.bin.is_empty() {
+      Some(self.root.join("src/main.fl"))
+        .filter(|path| path.is_file())
+        .map(|root| (name.clone(), root))
+        .into_iter()
+        .collect()
+    } else {
+      self
+        .manifest
+        .bin
+        .iter()
+        .map(|bin| (bin.name.clone(), self.root.join(&bin.path)))
+        .collect()
+    };
+
+    lib
+      .map(|root| Target {
+        kind: TargetKind::Lib,
+        name: name.clone(),
+        root,
+      })
+      .into_iter()
+      .chain(bins.into_iter().map(|(name, root)| Target {
+        kind: TargetKind::Bin,
+        name,
+        root,
+      }))
+      .collect()
+  }
+
+  /// All `.fl` files below the source roots, in a stable order.
+  pub fn source_files(&self) -> Result<Vec<PathBuf>, ManifestError> {
+    let mut files = Vec::new();
+
+    for source_root in &self.manifest.package.source_roots {
+      collect_sources(&self.root.join(source_root), &mut files)?;
+    }
+
+    files.sort();
+    Ok(files)
+  }
+}
+
+fn collect_sources(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), ManifestError> {
+  let entries = fs::read_dir(dir).map_err(|err| ManifestError::Io(dir.to_path_buf(), err))?;
+
+  for entry in entries {
+    let path = entry
+      .map_err(|err| ManifestError::Io(dir.to_path_buf(), err))?
+      .path();
+
+    if path.is_dir() {
+      collect_sources(&path, files)?;
+    } else if path.extension().is_some_and(|ext| ext == "fl") {
+      files.push(path);
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parse_minimal() {
+    let manifest: Manifest = toml::from_str(
+      r#"
+      [package]
+      name = "hello"
+      version = "0.1.0"
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(manifest.package.name, "hello");
+    assert_eq!(manifest.package.source_roots, vec![PathBuf::from("src")]);
+    assert_eq!(manifest.package.target, None);
+    assert_eq!(manifest.package.opt_level, None);
+    assert_eq!(manifest.package.automatic_semicolons, None);
+    assert_eq!(manifest.lib, None);
+    assert!(manifest.bin.is_empty());
+    assert!(manifest.dependencies.is_empty());
+  }
+
+  #[test]
+  fn parse_full() {
+    let manifest: Manifest = toml::from_str(
+      r#"
+      [package]
+      name = "hello"
+      version = "0.1.0"
+      source-roots = ["src", "gen"]
+      target = "x86_64-linux"
+      opt-level = 2
+      automatic-semicolons = true
+
+      [lib]
+      path = "src/hello.fl"
+
+      [[bin]]
+      name = "greet"
+      path = "src/bin/greet.fl"
+
+      [dependencies]
+      math = { path = "../math" }
+      "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+      manifest.package.source_roots,
+      vec![PathBuf::from("src"), PathBuf::from("gen")]
+    );
+    assert_eq!(manifest.package.target.as_deref(), Some("x86_64-linux"));
+    assert_eq!(manifest.package.opt_level, Some(OptLevel(2)));
+    assert_eq!(manifest.package.automatic_semicolons, Some(true));
+    assert_eq!(
+      manifest.lib,
+      Some(LibTarget {
+        path: PathBuf::from("src/hello.fl"),
+      })
+    );
+    assert_eq!(
+      manifest.bin,
+      [BinTarget {
+        name: "greet".to_string(),
+        path: PathBuf::from("src/bin/greet.fl"),
+      }]
+    );
+    assert_eq!(
+      manifest.dependencies["math"],
+      Dependency {
+        path: Some(PathBuf::from("../math")),
+        version: None,
+      }
+    );
+  }
+
+  fn write_package(dir: &Path, name: &str, dependencies: &[&str]) {
+    let dependencies: String = dependencies
+      .iter()
+      .map(|dependency| format!("{} = {{ path = \"../{}\" }}\n", dependency, dependency))
+      .collect();
+
+    fs::create_dir_all(dir.join(name)).unwrap();
+    fs::write(
+      dir.join(name).join(MANIFEST_FILE),
+      format!(
+        "[package]\nname = \"{}\"\nversion = \"0.1.0\"\n\n[dependencies]\n{}",
+        name, dependencies
+      ),
+    )
+    .unwrap();
+  }
+
+  fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("flc-manifest-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+  }
+
+  #[test]
+  fn resolve_dependencies() {
+    let dir = scratch_dir("resolve_dependencies");
+    write_package(&dir, "app", &["io", "math"]);
+    write_package(&dir, "io", &["core"]);
+    write_package(&dir, "math", &["core"]);
+    write_package(&dir, "core", &[]);
+
+    let app = Project::load(&dir.join("app").join(MANIFEST_FILE)).unwrap();
+    let names: Vec<_> = app
+      .resolve_dependencies()
+      .unwrap()
+      .into_iter()
+      .map(|project| project.manifest.package.name)
+      .collect();
+
+    assert_eq!(names, ["core", "io", "math"]);
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn dependency_cycle() {
+    let dir = scratch_dir("dependency_cycle");
+    write_package(&dir, "app", &["a"]);
+    write_package(&dir, "a", &["b"]);
+    write_package(&dir, "b", &["a"]);
+
+    let app = Project::load(&dir.join("app").join(MANIFEST_FILE)).unwrap();
+
+    assert_eq!(
+      app.resolve_dependencies().unwrap_err().to_string(),
+      "dependency cycle: a -> b -> a"
+    );
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn infer_targets() {
+    let dir = scratch_dir("infer_targets");
+    write_package(&dir, "app", &[]);
+    fs::create_dir_all(dir.join("app/src")).unwrap();
+    fs::write(dir.join("app/src/main.fl"), "main();\n").unwrap();
+    fs::write(dir.join("app/src/lib.fl"), "").unwrap();
+
+    let app = Project::load(&dir.join("app").join(MANIFEST_FILE)).unwrap();
+    let targets: Vec<_> = app
+      .targets()
+      .into_iter()
+      .map(|target| (target.kind, target.name, target.root))
+      .collect();
+
+    assert_eq!(
+      targets,
+      [
+        (
+          TargetKind::Lib,
+          "app".to_string(),
+          dir.join("app/src/lib.fl")
+        ),
+        (
+          TargetKind::Bin,
+          "app".to_string(),
+          dir.join("app/src/main.fl")
+        ),
+      ]
+    );
+    fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn reject_invalid() {
+    let opt_level = toml::from_str::<Manifest>(
+      r#"
+      [package]
+      name = "hello"
+      version = "0.1.0"
+      opt-level = 4
+      "#,
+    );
+    let unknown_key = toml::from_str::<Manifest>(
+      r#"
+      [package]
+      name = "hello"
+      version = "0.1.0"
+      edition = "2021"
+      "#,
+    );
+
+    assert!(opt_level.is_err());
+    assert!(unknown_key.is_err());
+  }
+
+  #[test]
+  fn reject_invalid_names() {
+    let dir = scratch_dir("reject_invalid_names");
+    write_package(&dir, "my-lib", &[]);
+    write_package(&dir, "tools", &[]);
+    let tools = dir.join("tools").join(MANIFEST_FILE);
+    let text = fs::read_to_string(&tools).unwrap();
+    fs::write(
+      &tools,
+      text + "\n[[bin]]\nname = \"mod\"\npath = \"src/main.fl\"\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+      Project::load(&dir.join("my-lib").join(MANIFEST_FILE))
+        .unwrap_err()
+        .to_string(),
+      format!(
+        "{}: package `my-lib` is not a valid name, it has to be an identifier that isn't a keyword",
+        dir.join("my-lib").join(MANIFEST_FILE).display()
+      )
+    );
+    assert!(Project::load(&tools).unwrap_err().to_string().ends_with(
+      "binary `mod` is not a valid name, it has to be an identifier that isn't a keyword"
+    ));
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,305 @@
+//! Shrinking inputs that crash the compiler into minimal reproductions.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use fll::tokenizer::{CaretPos, Tokenizer};
+
+use crate::exit_code;
+
+/// Delta debugging: repeatedly removes chunks of `input`, halving the chunk
+/// size whenever nothing can be removed, for as long as `reproduces` still
+/// holds. The result is 1-minimal, removing any single element makes the
+/// failure disappear.
+pub fn ddmin<T: Clone>(input: &[T], mut reproduces: impl FnMut(&[T]) -> bool) -> Vec<T> {
+  let mut input = input.to_vec();
+  let mut granularity = 2;
+
+  while input.len() >= 2 {
+    let chunk = input.len().div_ceil(granularity);
+    let mut reduced = false;
+
+    for start in (0..input.len()).step_by(chunk) {
+      let end = (start + chunk).min(input.len());
+      let candidate: Vec<T> = input[..start]
+        .iter()
+        .chain(&input[end..])
+        .cloned()
+        .collect();
+
+      if reproduces(&candidate) {
+        input = candidate;
+        granularity = (granularity - 1).max(2);
+        reduced = true;
+        break;
+      }
+    }
+
+    if !reduced {
+      if granularity >= input.len() {
+        break;
+      }
+      granularity = (granularity * 2).min(input.len());
+    }
+  }
+
+  input
+}
+
+/// Runs `flc check` on sources in a scratch file and reports the panic
+/// message if it crashed. Positions in the scratch file are left out of the
+/// message, removing text in front of the crash moves it but doesn't make it
+/// a different one.
+pub struct CrashOracle {
+  flc: PathBuf,
+  scratch: PathBuf,
+  treat_err_as_bug: bool,
+}
+
+impl CrashOracle {
+  /// With `treat_err_as_bug`, compile errors count as crashes too.
+  pub fn new(treat_err_as_bug: bool) -> std::io::Result<Self> {
+    Ok(Self {
+      flc: std::env::current_exe()?,
+      scratch: std::env::temp_dir().join(format!("flc-minimize-{}.fl", std::process::id())),
+      treat_err_as_bug,
+    })
+  }
+
+  /// The panic message if checking `source` ends in an internal compiler
+  /// error, `None` otherwise.
+  pub fn crash(&self, source: &str) -> std::io::Result<Option<String>> {
+    fs::write(&self.scratch, source)?;
+
+    let mut command = Command::new(&self.flc);
+    command.args(["check", "--no-ice-bundle", "--jobs", "1"]);
+    if self.treat_err_as_bug {
+      command.arg("--treat-err-as-bug");
+    }
+
+    let output = command
+      .arg(&self.scratch)
+      .env("RUST_BACKTRACE", "0")
+      .env_remove("FADE_FLAGS")
+      .output()?;
+
+    if output.status.code() != Some(exit_code::INTERNAL_COMPILER_ERROR) {
+      return Ok(None);
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let message = panic_message(&stderr).unwrap_or_default();
+    Ok(Some(without_location(&message, &self.scratch)))
+  }
+
+  pub fn scratch(&self) -> &Path {
+    &self.scratch
+  }
+}
+
+impl Drop for CrashOracle {
+  fn drop(&mut self) {
+    let _ = fs::remove_file(&self.scratch);
+  }
+}
+
+/// The message of the default panic output, i.e. the lines between
+/// `thread '...' panicked at <location>:` and the first `note:`.
+fn panic_message(stderr: &str) -> Option<String> {
+  let mut lines = stderr
+    .lines()
+    .skip_while(|line| !line.contains("panicked at"));
+  lines.next()?;
+
+  let message: Vec<_> = lines
+    .take_while(|line| !line.starts_with("note:"))
+    .collect();
+  Some(message.join("\n"))
+}
+
+/// `message` with every `<path>:<line>:<column>` of `path` replaced by just
+/// the path.
+fn without_location(message: &str, path: &Path) -> String {
+  let path = path.display().to_string();
+  let mut pieces = message.split(path.as_str());
+  let mut stripped = pieces.next().unwrap_or_default().to_string();
+
+  for piece in pieces {
+    let mut rest = piece;
+    for _ in 0..2 {
+      let digits = rest
+        .strip_prefix(':')
+        .map(|after| after.len() - after.trim_start_matches(|c: char| c.is_ascii_digit()).len());
+      match digits {
+        Some(digits) if digits > 0 => rest = &rest[1 + digits..],
+        _ => break,
+      }
+    }
+    stripped.push_str(&path);
+    stripped.push_str(rest);
+  }
+  stripped
+}
+
+/// `source` cut into its tokens, so that removing a piece never splits one.
+/// Where the tokenizer fails, the characters it rejects become a piece of
+/// their own and it starts over behind them. Text in front of them that
+/// doesn't lex on its own, like the start of a string, is kept whole.
+fn tokens(source: &str) -> Vec<&str> {
+  let mut pieces = Vec::new();
+  let mut rest = source;
+
+  while !rest.is_empty() {
+    let (lexed, rejected) = match Tokenizer::default().tokenize_str(rest) {
+      Ok(_) => (rest.len(), 0),
+      Err(err) => {
+        let start = byte_offset(rest, err.pos);
+        let width = rest[start..]
+          .char_indices()
+          .nth(err.width.max(1))
+          .map_or(rest.len() - start, |(offset, _)| offset);
+        (start, width)
+      }
+    };
+
+    match Tokenizer::default().tokenize_str(&rest[..lexed]) {
+      Ok(tokens) => pieces.extend(
+        tokens
+          .iter()
+          .map(|(_, span)| &rest[span.byte_range.clone()])
+          .filter(|piece| !piece.is_empty()),
+      ),
+      Err(_) => pieces.push(&rest[..lexed]),
+    }
+    if rejected > 0 {
+      pieces.push(&rest[lexed..lexed + rejected]);
+    }
+    rest = &rest[lexed + rejected..];
+  }
+  pieces
+}
+
+/// Where `pos` is in `text`, in bytes.
+fn byte_offset(text: &str, pos: CaretPos) -> usize {
+  let line_start: usize = text
+    .split_inclusive('\n')
+    .take(pos.get_line() - 1)
+    .map(str::len)
+    .sum();
+  text[line_start..]
+    .char_indices()
+    .nth(pos.get_column() - 1)
+    .map_or(text.len(), |(offset, _)| line_start + offset)
+}
+
+/// Shrinks `source` to a minimal input crashing with the same panic message,
+/// first by whole lines, then by tokens and then by characters.
+pub fn minimize(source: &str, oracle: &CrashOracle) -> std::io::Result<Option<String>> {
+  let Some(expected) = oracle.crash(source)? else {
+    return Ok(None);
+  };
+
+  let mut error = None;
+  let mut reproduces = |candidate: &str| match oracle.crash(candidate) {
+    Ok(message) => message.as_ref() == Some(&expected),
+    Err(err) => {
+      error.get_or_insert(err);
+      false
+    }
+  };
+
+  let lines: Vec<&str> = source.split_inclusive('\n').collect();
+  let lines = ddmin(&lines, |lines| reproduces(&lines.concat())).concat();
+
+  let tokens = ddmin(&tokens(&lines), |tokens| reproduces(&tokens.concat())).concat();
+
+  let chars: Vec<char> = tokens.chars().collect();
+  let chars: String = ddmin(&chars, |chars| {
+    reproduces(&chars.iter().collect::<String>())
+  })
+  .into_iter()
+  .collect();
+
+  match error {
+    Some(err) => Err(err),
+    None => Ok(Some(chars)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn ddmin_single_culprit() {
+    let input: Vec<u32> = (0..100).collect();
+
+    assert_eq!(ddmin(&input, |candidate| candidate.contains(&42)), [42]);
+  }
+
+  #[test]
+  fn ddmin_multiple_culprits() {
+    let input: Vec<char> = "main(); x = 1; other();".chars().collect();
+    let minimized = ddmin(&input, |candidate| {
+      candidate.contains(&'x') && candidate.contains(&'=')
+    });
+
+    assert_eq!(minimized, ['x', '=']);
+  }
+
+  #[test]
+  fn ddmin_keeps_order() {
+    let input: Vec<char> = "abcdef".chars().collect();
+    let minimized = ddmin(&input, |candidate| {
+      let text: String = candidate.iter().collect();
+      text.contains("ce") || text.contains("bd")
+    });
+
+    assert!(minimized == ['c', 'e'] || minimized == ['b', 'd']);
+  }
+
+  #[test]
+  fn parse_panic_message() {
+    let stderr = "\nthread 'main' (1) panicked at fll/src/session.rs:50:33:\n\
+                  x.fl:1:3: unexpected character `=`\n\
+                  note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n";
+
+    assert_eq!(
+      panic_message(stderr).as_deref(),
+      Some("x.fl:1:3: unexpected character `=`")
+    );
+    assert_eq!(panic_message("error: x.fl: No such file"), None);
+  }
+
+  #[test]
+  fn strip_location() {
+    let path = Path::new("/tmp/flc-minimize-1.fl");
+
+    assert_eq!(
+      without_location("/tmp/flc-minimize-1.fl:3:5: unexpected character `$`", path),
+      "/tmp/flc-minimize-1.fl: unexpected character `$`"
+    );
+    assert_eq!(
+      without_location("/tmp/flc-minimize-1.fl: No such file", path),
+      "/tmp/flc-minimize-1.fl: No such file"
+    );
+    assert_eq!(
+      without_location("index out of bounds", path),
+      "index out of bounds"
+    );
+  }
+
+  #[test]
+  fn split_into_tokens() {
+    assert_eq!(
+      tokens("x = 12u8;\n"),
+      ["x", " ", "=", " ", "12u8", ";", "\n"]
+    );
+    assert_eq!(
+      tokens("main();\nx = $y;\n"),
+      ["main", "(", ")", ";", "\n", "x", " ", "=", " ", "$", "y", ";", "\n"]
+    );
+    assert_eq!(tokens("s = \"a\\qb\";"), ["s = \"a", "\\q", "b", "\"", ";"]);
+  }
+}
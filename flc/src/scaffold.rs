@@ -0,0 +1,111 @@
+//! Project skeletons for `flc new` and `flc init`.
+
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use fll::token::is_keyword;
+
+use crate::manifest::MANIFEST_FILE;
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Template {
+  /// A program, starting at `main` in `src/main.fl`.
+  #[default]
+  Bin,
+  /// A package for other packages to depend on, starting at `src/lib.fl`.
+  Lib,
+}
+
+impl Display for Template {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Template::Bin => write!(f, "binary"),
+      Template::Lib => write!(f, "library"),
+    }
+  }
+}
+
+/// Package and binary names end up in paths of other packages' code and in
+/// mangled symbols, so they have to be identifiers.
+pub fn check_name(name: &str) -> Result<(), String> {
+  let mut chars = name.chars();
+  let valid = chars
+    .next()
+    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    && !is_keyword(name);
+
+  if valid {
+    Ok(())
+  } else {
+    Err(format!(
+      "`{}` is not a valid name, it has to be an identifier that isn't a keyword",
+      name
+    ))
+  }
+}
+
+/// The files of a new package, relative to its root.
+pub fn files(name: &str, template: Template) -> Vec<(PathBuf, String)> {
+  let source = match template {
+    Template::Bin => (
+      "src/main.fl",
+      "main() {\n  println(\"Hello, world!\");\n};\n",
+    ),
+    Template::Lib => (
+      "src/lib.fl",
+      "greeting(): -> string {\n  return \"Hello, world!\";\n};\n",
+    ),
+  };
+
+  [
+    (
+      MANIFEST_FILE,
+      format!("[package]\nname = \"{}\"\nversion = \"0.1.0\"\n", name),
+    ),
+    (source.0, source.1.to_string()),
+    // Git doesn't track empty directories.
+    ("test/.gitkeep", String::new()),
+    (".gitignore", "/out/\n".to_string()),
+  ]
+  .into_iter()
+  .map(|(path, contents)| (PathBuf::from(path), contents))
+  .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use fll::api::{compile, CompileOptions, SourceInput};
+
+  use super::*;
+  use crate::manifest::Manifest;
+
+  #[test]
+  fn names() {
+    for name in ["hello", "my_project", "_private", "math2"] {
+      assert_eq!(check_name(name), Ok(()), "{}", name);
+    }
+    for name in ["", "my-project", "2d", "mod", "héllo"] {
+      assert!(check_name(name).is_err(), "{}", name);
+    }
+  }
+
+  #[test]
+  fn templates_compile() {
+    for template in [Template::Bin, Template::Lib] {
+      let files = files("hello", template);
+      let manifest: Manifest = toml::from_str(&files[0].1).unwrap();
+
+      assert_eq!(manifest.package.name, "hello");
+      assert!(
+        compile(
+          SourceInput::text(files[1].1.clone()),
+          CompileOptions::default()
+        )
+        .is_ok(),
+        "{}",
+        template
+      );
+    }
+  }
+}
@@ -0,0 +1,84 @@
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use fll::api::{Observer, Phase};
+
+use crate::ice::{self, PhaseGuard};
+
+/// Reports how long each compiler phase took when `--time-passes` is given.
+///
+/// The phases of the front-end are reported by the session it observes,
+/// flc's own phases go through `time`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PassTimer {
+  enabled: bool,
+}
+
+impl PassTimer {
+  pub fn new(enabled: bool) -> Self {
+    Self { enabled }
+  }
+
+  /// Runs `f` as the phase `name` for `input`, printing its wall time and the
+  /// peak memory use of the process afterwards.
+  ///
+  /// This also records the phase for ICE reports and traces it.
+  pub fn time<T>(&self, name: &'static str, input: &Path, f: impl FnOnce() -> T) -> T {
+    let _phase = PhaseGuard::enter(name, input);
+    let _span = tracing::info_span!("pass", pass = name, input = %input.display()).entered();
+
+    let start = Instant::now();
+    let result = f();
+    self.report(name, input, start.elapsed());
+    result
+  }
+
+  fn report(&self, name: &str, input: &Path, elapsed: Duration) {
+    if !self.enabled {
+      return;
+    }
+
+    match peak_rss_kib() {
+      Some(peak) => eprintln!(
+        "time: {:>10.6}s; peak rss: {:>7}KiB\t{}\t{}",
+        elapsed.as_secs_f64(),
+        peak,
+        name,
+        input.display()
+      ),
+      None => eprintln!(
+        "time: {:>10.6}s\t{}\t{}",
+        elapsed.as_secs_f64(),
+        name,
+        input.display()
+      ),
+    }
+  }
+}
+
+impl Observer for PassTimer {
+  fn on_phase_start(&self, phase: Phase, path: &Path) {
+    ice::enter_phase(phase.name(), path);
+  }
+
+  fn on_phase_finish(&self, phase: Phase, path: &Path, elapsed: Duration) {
+    ice::leave_phase();
+    self.report(phase.name(), path, elapsed);
+  }
+}
+
+/// The peak resident set size of this process, where the platform exposes it.
+#[cfg(target_os = "linux")]
+fn peak_rss_kib() -> Option<u64> {
+  let status = std::fs::read_to_string("/proc/self/status").ok()?;
+
+  status
+    .lines()
+    .find_map(|line| line.strip_prefix("VmHWM:"))
+    .and_then(|value| value.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peak_rss_kib() -> Option<u64> {
+  None
+}
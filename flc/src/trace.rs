@@ -0,0 +1,55 @@
+//! Tracing output for debugging the compiler itself.
+//!
+//! `FLC_LOG` takes an env-filter directive such as `debug` or `fll=trace` and
+//! logs matching spans and events to stderr. `--trace-output` writes every
+//! span to a file in the folded stack format flamegraph tools read.
+
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal};
+use std::path::Path;
+
+use tracing_flame::{FlameLayer, FlushGuard};
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::EnvFilter;
+
+pub const LOG_ENV: &str = "FLC_LOG";
+
+/// Keeps the trace output open, dropping or flushing it writes what's left.
+pub type TraceGuard = FlushGuard<BufWriter<File>>;
+
+/// Installs the global subscriber. Does nothing if neither `FLC_LOG` nor an
+/// output file is given.
+pub fn init(output: Option<&Path>) -> Result<Option<TraceGuard>, String> {
+  let log = match std::env::var(LOG_ENV) {
+    Ok(directives) => Some(
+      EnvFilter::try_new(&directives).map_err(|err| format!("invalid `{}`: {}", LOG_ENV, err))?,
+    ),
+    Err(_) => None,
+  };
+
+  if log.is_none() && output.is_none() {
+    return Ok(None);
+  }
+
+  let log = log.map(|filter| {
+    tracing_subscriber::fmt::layer()
+      .with_writer(std::io::stderr)
+      .with_ansi(std::io::stderr().is_terminal())
+      .with_span_events(FmtSpan::CLOSE)
+      .with_filter(filter)
+  });
+
+  let (flame, guard) = match output {
+    Some(path) => {
+      let (layer, guard) =
+        FlameLayer::with_file(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+      let layer = layer.with_threads_collapsed(true).with_file_and_line(false);
+      (Some(layer), Some(guard))
+    }
+    None => (None, None),
+  };
+
+  tracing_subscriber::registry().with(log).with(flame).init();
+  Ok(guard)
+}
@@ -0,0 +1,91 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::command::CommandResult;
+use crate::ice;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// The last modification time of every watched file, `None` if it could not
+/// be read (e.g. the file is missing or in the middle of being saved).
+#[derive(Debug, Eq, PartialEq)]
+struct Snapshot {
+  modified: HashMap<PathBuf, Option<SystemTime>>,
+}
+
+impl Snapshot {
+  fn take(paths: &[PathBuf]) -> Self {
+    let modified = paths
+      .iter()
+      .map(|path| (path.clone(), modified(path)))
+      .collect();
+
+    Self { modified }
+  }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+  fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn clear_screen() {
+  print!("\x1b[2J\x1b[H");
+  let _ = std::io::stdout().flush();
+}
+
+/// Runs `f` and then again every time one of `paths` changes on disk. Never
+/// returns; the process is meant to be stopped with Ctrl-C.
+pub fn watch(paths: &[PathBuf], mut f: impl FnMut() -> CommandResult) -> ! {
+  loop {
+    let snapshot = Snapshot::take(paths);
+
+    clear_screen();
+    match panic::catch_unwind(AssertUnwindSafe(&mut f)) {
+      Ok(Ok(())) => eprintln!("[watch] ok, waiting for changes"),
+      Ok(Err(err)) => {
+        eprintln!("error: {}", err);
+        eprintln!("[watch] waiting for changes");
+      }
+      Err(_) => {
+        ice::clear_phases();
+        eprintln!("[watch] compiler panicked, waiting for changes");
+      }
+    }
+
+    while Snapshot::take(paths) == snapshot {
+      thread::sleep(POLL_INTERVAL);
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn snapshot_detects_changes() {
+    let dir = std::env::temp_dir().join(format!("flc-watch-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("main.fl");
+    let paths = [path.clone()];
+
+    let missing = Snapshot::take(&paths);
+    fs::write(&path, "main();\n").unwrap();
+    let created = Snapshot::take(&paths);
+    assert_ne!(missing, created);
+    assert_eq!(created, Snapshot::take(&paths));
+
+    let file = fs::File::options().write(true).open(&path).unwrap();
+    file
+      .set_modified(SystemTime::now() + Duration::from_secs(1))
+      .unwrap();
+    assert_ne!(created, Snapshot::take(&paths));
+
+    fs::remove_dir_all(dir).unwrap();
+  }
+}
@@ -0,0 +1,810 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+
+fn flc(args: &[&str]) -> Output {
+  Command::new(env!("CARGO_BIN_EXE_flc"))
+    .args(args)
+    .output()
+    .expect("failed to run flc")
+}
+
+fn scratch_dir(name: &str) -> PathBuf {
+  let dir = std::env::temp_dir().join(format!("flc-test-{}-{}", name, std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  dir
+}
+
+const MAIN_FN_TOKENS: &str = "Identifier { identifier: \"main\" }\n\
+                              Parenthesis { bracket_type: Opening }\n\
+                              Parenthesis { bracket_type: Closing }\n\
+                              Operator { operator_type: StatementTerminator }\n\
+                              NewLine\n\
+                              EndOfFile\n";
+
+#[test]
+fn tokenize_main_fn() {
+  let output = flc(&["tokenize", "test/cli/main_fn.fl"]);
+
+  assert!(output.status.success());
+  assert_eq!(String::from_utf8_lossy(&output.stdout), MAIN_FN_TOKENS);
+}
+
+#[test]
+fn parse_main_fn() {
+  let output = flc(&["parse", "test/cli/main_fn.fl"]);
+
+  assert!(output.status.success());
+  let ast = String::from_utf8_lossy(&output.stdout);
+  assert!(ast.starts_with("Module {\n"));
+  assert!(ast.contains("name: \"main\""));
+}
+
+#[test]
+fn parse_invalid_file() {
+  let output = flc(&["parse", "test/cli/crash.fl"]);
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn check_add() {
+  let output = flc(&["check", "test/cli/add.fl"]);
+
+  assert!(output.status.success());
+  assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn check_missing_file() {
+  let output = flc(&["check", "test/cli/missing.fl"]);
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: test/cli/missing.fl"));
+}
+
+#[test]
+fn missing_subcommand() {
+  let output = flc(&[]);
+
+  assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn emit_tokens() {
+  let dir = scratch_dir("emit_tokens");
+  let input = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/main_fn.fl");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&dir)
+    .args(["check", "--emit=tokens", input.to_str().unwrap()])
+    .output()
+    .unwrap();
+
+  assert!(output.status.success());
+  assert_eq!(
+    fs::read_to_string(dir.join("main_fn.tokens")).unwrap(),
+    MAIN_FN_TOKENS
+  );
+}
+
+#[test]
+fn emit_ast() {
+  let dir = scratch_dir("emit_ast");
+  let input = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/main_fn.fl");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&dir)
+    .args(["check", "--emit=ast", input.to_str().unwrap()])
+    .output()
+    .unwrap();
+
+  assert!(output.status.success());
+  let ast = fs::read_to_string(dir.join("main_fn.ast")).unwrap();
+  assert!(ast.starts_with("Module {\n"));
+  assert!(ast.contains("name: \"main\""));
+}
+
+#[test]
+fn emit_tokens_to_stdout() {
+  let output = flc(&["check", "--emit=tokens=-", "test/cli/main_fn.fl"]);
+
+  assert!(output.status.success());
+  assert_eq!(String::from_utf8_lossy(&output.stdout), MAIN_FN_TOKENS);
+}
+
+#[test]
+fn emit_unknown_kind() {
+  let output = flc(&["check", "--emit=asm", "test/cli/main_fn.fl"]);
+
+  assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn check_multiple_files() {
+  let output = flc(&["check", "test/cli/add.fl", "test/cli/main_fn.fl"]);
+
+  assert!(output.status.success());
+}
+
+#[test]
+fn check_continues_after_failure() {
+  let dir = scratch_dir("check_continues_after_failure");
+  let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&dir)
+    .arg("check")
+    .arg("--emit=tokens")
+    .arg(manifest_dir.join("test/cli/missing.fl"))
+    .arg(manifest_dir.join("test/cli/main_fn.fl"))
+    .output()
+    .unwrap();
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("could not check 1 of 2 files"));
+  assert_eq!(
+    fs::read_to_string(dir.join("main_fn.tokens")).unwrap(),
+    MAIN_FN_TOKENS
+  );
+}
+
+#[test]
+fn emit_path_with_multiple_files() {
+  let output = flc(&[
+    "check",
+    "--emit=tokens=out.tokens",
+    "test/cli/add.fl",
+    "test/cli/main_fn.fl",
+  ]);
+
+  assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn emit_to_output_path() {
+  let dir = scratch_dir("emit_to_output_path");
+  let output_path = dir.join("main.tok");
+
+  let output = flc(&[
+    "check",
+    "--emit=tokens",
+    "-o",
+    output_path.to_str().unwrap(),
+    "test/cli/main_fn.fl",
+  ]);
+
+  assert!(output.status.success());
+  assert_eq!(fs::read_to_string(output_path).unwrap(), MAIN_FN_TOKENS);
+}
+
+#[test]
+fn emit_to_out_dir() {
+  let dir = scratch_dir("emit_to_out_dir");
+  let out_dir = dir.join("build");
+
+  let output = flc(&[
+    "check",
+    "--emit=tokens",
+    "--out-dir",
+    out_dir.to_str().unwrap(),
+    "test/cli/add.fl",
+    "test/cli/main_fn.fl",
+  ]);
+
+  assert!(output.status.success());
+  assert!(out_dir.join("add.tokens").is_file());
+  assert_eq!(
+    fs::read_to_string(out_dir.join("main_fn.tokens")).unwrap(),
+    MAIN_FN_TOKENS
+  );
+}
+
+#[test]
+fn output_path_with_multiple_files() {
+  let output = flc(&[
+    "check",
+    "--emit=tokens",
+    "-o",
+    "out.tokens",
+    "test/cli/add.fl",
+    "test/cli/main_fn.fl",
+  ]);
+
+  assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn time_passes() {
+  let output = flc(&["check", "--time-passes", "test/cli/main_fn.fl"]);
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert!(output.status.success());
+  for phase in ["lex", "parse", "emit"] {
+    assert_eq!(
+      stderr
+        .lines()
+        .filter(|line| line.starts_with("time:") && line.contains(&format!("\t{}\t", phase)))
+        .count(),
+      1,
+      "{}",
+      phase
+    );
+  }
+}
+
+#[test]
+fn tokenize_without_parsing() {
+  let dir = scratch_dir("tokenize_without_parsing");
+  let input = dir.join("t.fl");
+  fs::write(&input, "x = 1;\n").unwrap();
+
+  let output = flc(&["tokenize", input.to_str().unwrap()]);
+
+  assert!(output.status.success());
+  assert!(String::from_utf8_lossy(&output.stdout).starts_with("Identifier { identifier: \"x\" }\n"));
+  assert!(!flc(&["check", input.to_str().unwrap()]).status.success());
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn trace_output() {
+  let dir = scratch_dir("trace_output");
+  let trace = dir.join("trace.folded");
+  fs::create_dir_all(&dir).unwrap();
+
+  let output = flc(&[
+    "check",
+    "--trace-output",
+    trace.to_str().unwrap(),
+    "test/cli/main_fn.fl",
+  ]);
+  let folded = fs::read_to_string(&trace).unwrap();
+
+  assert!(output.status.success());
+  assert!(folded
+    .lines()
+    .any(|line| line.contains("fll::session::compile; fll::session::lex ")));
+}
+
+#[test]
+fn unhandled_char() {
+  let output = flc(&["check", "test/cli/unhandled_char.fl"]);
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(stderr.contains("error: test/cli/unhandled_char.fl:1:5: unexpected character `$`"));
+}
+
+#[test]
+fn internal_compiler_error() {
+  let output = flc(&["check", "--treat-err-as-bug", "test/cli/unhandled_char.fl"]);
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(101));
+  assert!(stderr.contains("error: internal compiler error"));
+  assert!(stderr.contains("phase `lex` for `test/cli/unhandled_char.fl`"));
+
+  let bundle = stderr
+    .lines()
+    .find_map(|line| line.strip_prefix("note: a reproduction bundle was written to `"))
+    .and_then(|line| line.split('`').next())
+    .map(PathBuf::from)
+    .expect("no reproduction bundle reported");
+  let report = fs::read_to_string(bundle.join("report.txt")).unwrap();
+
+  assert!(report.contains("phase: lex"));
+  assert!(report.contains("unexpected character `$`"));
+  assert_eq!(
+    fs::read_to_string(bundle.join("sources/unhandled_char.fl")).unwrap(),
+    "x = $y;\n"
+  );
+  fs::remove_dir_all(bundle).unwrap();
+}
+
+#[test]
+fn internal_compiler_error_without_bundle() {
+  let output = flc(&[
+    "check",
+    "--no-ice-bundle",
+    "--treat-err-as-bug",
+    "test/cli/unhandled_char.fl",
+  ]);
+  let stderr = String::from_utf8_lossy(&output.stderr);
+
+  assert_eq!(output.status.code(), Some(101));
+  assert!(!stderr.contains("reproduction bundle"));
+}
+
+#[test]
+fn check_project() {
+  let dir = scratch_dir("check_project");
+  let project = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/project");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(project.join("src/util"))
+    .arg("check")
+    .arg("--emit=tokens")
+    .arg("--out-dir")
+    .arg(&dir)
+    .output()
+    .unwrap();
+
+  assert!(output.status.success());
+  assert!(dir.join("add.tokens").is_file());
+  assert_eq!(
+    fs::read_to_string(dir.join("main.tokens")).unwrap(),
+    MAIN_FN_TOKENS
+  );
+}
+
+#[test]
+fn new_project() {
+  let dir = scratch_dir("new_project");
+  let project = dir.join("hello");
+
+  let output = flc(&["new", project.to_str().unwrap()]);
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "created binary package `hello`\n"
+  );
+  assert!(project.join("src/main.fl").is_file());
+  assert!(project.join("test").is_dir());
+  assert!(project.join(".gitignore").is_file());
+
+  let check = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&project)
+    .arg("check")
+    .output()
+    .unwrap();
+  assert!(check.status.success());
+
+  let again = flc(&["new", project.to_str().unwrap()]);
+  assert_eq!(again.status.code(), Some(2));
+}
+
+#[test]
+fn init_project() {
+  let dir = scratch_dir("init_project");
+  fs::create_dir_all(dir.join("src")).unwrap();
+  fs::write(dir.join("src/lib.fl"), "main();\n").unwrap();
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&dir)
+    .args(["init", "--lib", "--name", "greeter"])
+    .output()
+    .unwrap();
+
+  assert!(output.status.success());
+  assert!(fs::read_to_string(dir.join("fade.toml"))
+    .unwrap()
+    .contains("name = \"greeter\"\n"));
+  assert_eq!(
+    fs::read_to_string(dir.join("src/lib.fl")).unwrap(),
+    "main();\n"
+  );
+
+  let invalid_name = flc(&["new", dir.join("my-lib").to_str().unwrap()]);
+  assert_eq!(invalid_name.status.code(), Some(2));
+  assert!(String::from_utf8_lossy(&invalid_name.stderr).contains("`my-lib` is not a valid"));
+  assert!(!dir.join("my-lib").exists());
+}
+
+/// Identical inputs have to give byte-identical artifacts, whatever the
+/// scheduling and however the inputs are named.
+#[test]
+fn deterministic_output() {
+  let inputs = [
+    "test/cli/add.fl",
+    "test/cli/main_fn.fl",
+    "test/cli/project/src/main.fl",
+    "test/cli/project/src/util/add.fl",
+  ];
+  let tokenize = |jobs: &str| flc(&[&["tokenize", "--jobs", jobs][..], &inputs].concat()).stdout;
+
+  assert_eq!(tokenize("1"), tokenize("8"));
+
+  let relative = scratch_dir("deterministic_output_relative");
+  let absolute = scratch_dir("deterministic_output_absolute");
+  let project = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/project");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&project)
+    .args(["check", "--emit=tokens", "--jobs", "1", "--out-dir"])
+    .arg(&relative)
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&absolute)
+    .args(["check", "--emit=tokens", "--jobs", "8", "--out-dir", "."])
+    .arg(project.join("src/main.fl"))
+    .arg(project.join("src/util/add.fl"))
+    .output()
+    .unwrap();
+  assert!(output.status.success());
+
+  for file in ["main.tokens", "add.tokens"] {
+    assert_eq!(
+      fs::read(relative.join(file)).unwrap(),
+      fs::read(absolute.join(file)).unwrap()
+    );
+  }
+}
+
+#[test]
+fn check_without_inputs_or_project() {
+  let dir = scratch_dir("check_without_inputs_or_project");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&dir)
+    .arg("check")
+    .output()
+    .unwrap();
+
+  assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn completions() {
+  for shell in ["bash", "zsh", "fish", "powershell"] {
+    let output = flc(&["completions", shell]);
+
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("tokenize"));
+  }
+
+  let bash = flc(&["completions", "bash"]);
+  assert!(String::from_utf8_lossy(&bash.stdout).contains("tokens"));
+}
+
+#[test]
+fn build_plan() {
+  let dir = scratch_dir("build_plan");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&dir)
+    .arg("check")
+    .arg("--build-plan")
+    .arg("--emit=tokens")
+    .arg(PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/main_fn.fl"))
+    .output()
+    .unwrap();
+  let stdout = String::from_utf8_lossy(&output.stdout);
+
+  assert!(output.status.success());
+  assert!(stdout.contains("\"path\": \"main_fn.tokens\""));
+  assert!(!dir.join("main_fn.tokens").exists());
+}
+
+#[test]
+fn tokenize_in_input_order() {
+  let mut args = vec!["tokenize", "--jobs", "4"];
+  for _ in 0..8 {
+    args.extend(["test/cli/add.fl", "test/cli/main_fn.fl"]);
+  }
+
+  let output = flc(&args);
+  let stdout = String::from_utf8_lossy(&output.stdout);
+  let add = String::from_utf8_lossy(&flc(&["tokenize", "test/cli/add.fl"]).stdout).into_owned();
+
+  assert!(output.status.success());
+  assert_eq!(stdout, (add + MAIN_FN_TOKENS).repeat(8));
+}
+
+#[test]
+fn config_show() {
+  let project = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/project");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&project)
+    .env("FADE_FLAGS", "--jobs 3 -O 1")
+    .args(["config", "show", "-O", "2"])
+    .output()
+    .unwrap();
+  let stdout = String::from_utf8_lossy(&output.stdout);
+
+  assert!(output.status.success());
+  assert!(stdout.contains("opt-level = 2  # command line\n"));
+  assert!(stdout.contains("jobs = 3  # FADE_FLAGS\n"));
+  assert!(stdout.contains("time-passes = false  # default\n"));
+  assert!(stdout.contains("automatic-semicolons = false  # default\n"));
+}
+
+#[test]
+fn invalid_manifest() {
+  let dir = scratch_dir("invalid_manifest");
+  fs::write(dir.join("fade.toml"), "[package]\nname = \"math\"\n").unwrap();
+  let flc_in_dir = |args: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_flc"))
+      .current_dir(&dir)
+      .args(args)
+      .output()
+      .unwrap()
+  };
+
+  let output = flc_in_dir(&["demangle", "_FN4math3addE"]);
+  assert!(output.status.success());
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "math::add\n");
+  assert!(flc_in_dir(&["new", "sub"]).status.success());
+  assert!(flc_in_dir(&["grammar", "ebnf"]).status.success());
+
+  let output = flc_in_dir(&["check"]);
+  assert_eq!(output.status.code(), Some(1));
+  assert!(String::from_utf8_lossy(&output.stderr).contains("version"));
+
+  fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn invalid_env_flags() {
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .env("FADE_FLAGS", "--bogus")
+    .args(["config", "show"])
+    .output()
+    .unwrap();
+
+  assert_eq!(output.status.code(), Some(2));
+  assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: invalid `FADE_FLAGS`"));
+}
+
+#[test]
+fn check_dependencies_first() {
+  let app = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/packages/app");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&app)
+    .arg("tokenize")
+    .output()
+    .unwrap();
+  let add = flc(&["tokenize", "test/cli/add.fl"]).stdout;
+
+  assert!(output.status.success());
+  assert_eq!(
+    output.stdout,
+    [add, MAIN_FN_TOKENS.as_bytes().to_vec()].concat()
+  );
+}
+
+#[test]
+fn dependency_manifest_options() {
+  let dir = scratch_dir("dependency_manifest_options");
+  let write = |path: &str, contents: &str| {
+    let path = dir.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, contents).unwrap();
+  };
+  write(
+    "lines/fade.toml",
+    "[package]\nname = \"lines\"\nversion = \"0.1.0\"\nautomatic-semicolons = true\n",
+  );
+  write("lines/src/lib.fl", "one(): -> u8 {\n  return 1\n}\n");
+  write(
+    "app/fade.toml",
+    "[package]\nname = \"app\"\nversion = \"0.1.0\"\n\n[dependencies]\nlines = { path = \"../lines\" }\n",
+  );
+  write("app/src/main.fl", "main() {\n  one()\n}\n");
+  let run = |args: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_flc"))
+      .current_dir(dir.join("app"))
+      .args(args)
+      .output()
+      .unwrap()
+  };
+
+  let check = run(&["check"]);
+  assert_eq!(check.status.code(), Some(1));
+  assert!(String::from_utf8_lossy(&check.stderr).contains("app/src/main.fl:3:1: expected `;`"));
+
+  write("app/src/main.fl", "main() {\n  one();\n};\n");
+  assert!(run(&["check"]).status.success());
+  assert!(
+    run(&["build", "--out-dir", dir.join("out").to_str().unwrap()])
+      .status
+      .success()
+  );
+}
+
+#[test]
+fn build_binary_and_dependency() {
+  let dir = scratch_dir("build_binary_and_dependency");
+  let app = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/packages/app");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&app)
+    .arg("build")
+    .arg("--out-dir")
+    .arg(&dir)
+    .output()
+    .unwrap();
+  let binary = fs::read_to_string(dir.join("app.fbin")).unwrap();
+
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "built library `math`\nbuilt binary `app`\n"
+  );
+  assert!(fs::read_to_string(dir.join("math.flib"))
+    .unwrap()
+    .contains("\"symbol\": \"_FN4math3addE\""));
+  assert!(binary.contains("\"entry\": \"_FN3app4mainE\""));
+  assert!(binary.contains("\"libraries\": [\n    \"math\"\n  ]"));
+}
+
+#[test]
+fn build_plan_with_dependency() {
+  let dir = scratch_dir("build_plan_with_dependency");
+  let app = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/packages/app");
+
+  let output = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .current_dir(&app)
+    .arg("build")
+    .arg("--build-plan")
+    .arg("--out-dir")
+    .arg(&dir)
+    .output()
+    .unwrap();
+  let stdout = String::from_utf8_lossy(&output.stdout);
+
+  assert!(output.status.success());
+  assert!(stdout.contains(&format!(
+    "\"path\": \"{}\"",
+    dir.join("math.flib").display()
+  )));
+  assert!(stdout.contains("\"phases\": [\n        \"read\",\n        \"lex\""));
+  assert!(stdout.contains("\"dependencies\": [\n        0\n      ]"));
+  assert!(!dir.join("app.fbin").exists());
+}
+
+#[test]
+fn build_binary_with_own_library() {
+  let dir = scratch_dir("build_binary_with_own_library");
+  let package = dir.join("tools");
+  fs::create_dir_all(package.join("src")).unwrap();
+  fs::write(
+    package.join("fade.toml"),
+    "[package]\nname = \"tools\"\nversion = \"0.1.0\"\n",
+  )
+  .unwrap();
+  fs::write(
+    package.join("src/lib.fl"),
+    "double(x: u8): -> u8 {\n  return x + x;\n};\n",
+  )
+  .unwrap();
+  fs::write(
+    package.join("src/main.fl"),
+    "main() {\n  println(double(2));\n};\n",
+  )
+  .unwrap();
+  let build = |args: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_flc"))
+      .current_dir(&package)
+      .arg("build")
+      .args(args)
+      .arg("--out-dir")
+      .arg(dir.join("out"))
+      .output()
+      .unwrap()
+  };
+
+  let output = build(&["--bin", "tools"]);
+  let binary = fs::read_to_string(dir.join("out/tools.fbin")).unwrap();
+
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "built library `tools`\nbuilt binary `tools`\n"
+  );
+  assert!(binary.contains("\"libraries\": [\n    \"tools\"\n  ]"));
+  assert!(binary.contains("\"imports\": [\n    \"_FN5tools6doubleE\"\n  ]"));
+
+  fs::write(package.join("src/main.fl"), "main() {\n  triple(2);\n};\n").unwrap();
+  let output = build(&[]);
+
+  assert_eq!(output.status.code(), Some(1));
+  assert!(String::from_utf8_lossy(&output.stderr)
+    .contains("binary `tools` calls functions that are defined nowhere: `triple`"));
+}
+
+#[test]
+fn build_lib() {
+  let dir = scratch_dir("build_lib");
+  let packages = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("test/cli/packages");
+  let build = |package: &str, args: &[&str]| {
+    Command::new(env!("CARGO_BIN_EXE_flc"))
+      .current_dir(packages.join(package))
+      .arg("build")
+      .args(args)
+      .arg("--out-dir")
+      .arg(&dir)
+      .output()
+      .unwrap()
+  };
+
+  let math = build("math", &["--lib"]);
+  assert!(math.status.success());
+  assert!(dir.join("math.flib").is_file());
+
+  let app = build("app", &["--lib"]);
+  assert_eq!(app.status.code(), Some(2));
+  assert!(String::from_utf8_lossy(&app.stderr).contains("package `app` has no library"));
+
+  let unknown_bin = build("app", &["--bin", "tool"]);
+  assert_eq!(unknown_bin.status.code(), Some(2));
+}
+
+#[test]
+fn grammar_textmate() {
+  let dir = scratch_dir("grammar_textmate");
+
+  let output = flc(&["grammar", "textmate", "--out-dir", dir.to_str().unwrap()]);
+  let grammar = fs::read_to_string(dir.join("fadelang.tmLanguage.json")).unwrap();
+
+  assert!(output.status.success());
+  assert!(grammar.contains("\"scopeName\": \"source.fadelang\""));
+  assert!(dir.join("language-configuration.json").is_file());
+}
+
+#[test]
+fn grammar_ebnf() {
+  let output = flc(&["grammar", "ebnf"]);
+  let grammar = String::from_utf8_lossy(&output.stdout);
+
+  assert!(output.status.success());
+  assert!(grammar.contains("\nidentifier ::= ( [a-zA-Z_] [a-zA-Z0-9_]* ) - keyword\n"));
+}
+
+#[test]
+fn demangle_symbols() {
+  let output = flc(&["demangle", "_FN4math3minIN2u8EEE", "printf"]);
+
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "math::min<u8>\nprintf\n"
+  );
+}
+
+#[test]
+fn demangle_stdin() {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_flc"))
+    .arg("demangle")
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .spawn()
+    .expect("failed to run flc");
+  child
+    .stdin
+    .take()
+    .unwrap()
+    .write_all(b"undefined reference to `_FN3res3minIN3i64EEE'\n  at _FN4mainE\n")
+    .unwrap();
+  let output = child.wait_with_output().unwrap();
+
+  assert!(output.status.success());
+  assert_eq!(
+    String::from_utf8_lossy(&output.stdout),
+    "undefined reference to `res::min<i64>'\n  at main\n"
+  );
+}
+
+#[test]
+fn minimize_crash() {
+  let output = flc(&["minimize", "--treat-err-as-bug", "test/cli/crash.fl"]);
+
+  assert!(output.status.success());
+  assert_eq!(String::from_utf8_lossy(&output.stdout), "$");
+}
+
+#[test]
+fn minimize_without_crash() {
+  let output = flc(&["minimize", "test/cli/main_fn.fl"]);
+
+  assert_eq!(output.status.code(), Some(1));
+}
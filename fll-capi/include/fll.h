@@ -0,0 +1,65 @@
+/*
+ * C bindings for the fadelang front-end.
+ *
+ * Link against the `fll_capi` library built from this crate. Strings passed
+ * in must be NUL-terminated UTF-8. No function unwinds into C, functions
+ * returning a pointer return NULL if something went wrong internally.
+ */
+
+#ifndef FLL_H
+#define FLL_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The outcome of a compilation, freed with `fll_result_free`. */
+typedef struct FllResult FllResult;
+
+/* A position in a source file, both counted from 1. */
+typedef struct FllPosition {
+  size_t line;
+  size_t column;
+} FllPosition;
+
+/* Compiles the file at `path`. Returns NULL if `path` is NULL. */
+FllResult *fll_compile_file(const char *path);
+
+/* Compiles `text` as the contents of a source file. Returns NULL if `text` is NULL. */
+FllResult *fll_compile_text(const char *text);
+
+/* Whether the compilation succeeded. */
+bool fll_result_is_ok(const FllResult *result);
+
+/* The number of tokens produced, 0 if the compilation failed. */
+size_t fll_result_token_count(const FllResult *result);
+
+/* The number of diagnostics reported, 0 if the compilation succeeded. */
+size_t fll_result_diagnostic_count(const FllResult *result);
+
+/*
+ * The message of the diagnostic at `index`, or NULL if there is none. The
+ * string belongs to `result` and lives until it is freed.
+ */
+const char *fll_result_diagnostic_message(const FllResult *result, size_t index);
+
+/*
+ * Writes where the diagnostic at `index` starts to `start`, and where it
+ * ends, right after its last character, to `end`. Returns false and writes
+ * nothing if there is no such diagnostic or it isn't about a part of the
+ * file.
+ */
+bool fll_result_diagnostic_range(const FllResult *result, size_t index, FllPosition *start,
+                                 FllPosition *end);
+
+/* Frees a result. Does nothing for NULL. */
+void fll_result_free(FllResult *result);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* FLL_H */
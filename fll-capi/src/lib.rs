@@ -0,0 +1,379 @@
+#![warn(clippy::all)]
+
+//! C bindings for `fll`, declared in `include/fll.h`.
+//!
+//! Every function catches panics, a panic never unwinds into C. Functions
+//! returning a pointer return `NULL` instead.
+
+use std::ffi::{c_char, CStr, CString};
+use std::ops::Range;
+use std::panic::{self, UnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+
+use fll::api::{Artifact, CaretPos, Session, SourceInput};
+
+/// The outcome of `fll_compile_file` or `fll_compile_text`.
+pub struct FllResult {
+  result: Result<Artifact, Vec<FllDiagnostic>>,
+}
+
+struct FllDiagnostic {
+  message: CString,
+  range: Option<Range<CaretPos>>,
+}
+
+/// A position in a source file, both counted from 1.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FllPosition {
+  pub line: usize,
+  pub column: usize,
+}
+
+impl From<CaretPos> for FllPosition {
+  fn from(pos: CaretPos) -> Self {
+    Self {
+      line: pos.get_line(),
+      column: pos.get_column(),
+    }
+  }
+}
+
+impl FllResult {
+  fn compile(input: SourceInput) -> Self {
+    let result = Session::default().compile(input).map_err(|diagnostics| {
+      diagnostics
+        .iter()
+        .map(|diagnostic| FllDiagnostic {
+          message: c_string(diagnostic.message()),
+          range: diagnostic.range(),
+        })
+        .collect()
+    });
+
+    Self { result }
+  }
+
+  fn error(message: &str) -> Self {
+    Self {
+      result: Err(vec![FllDiagnostic {
+        message: c_string(message),
+        range: None,
+      }]),
+    }
+  }
+
+  fn diagnostic(&self, index: usize) -> Option<&FllDiagnostic> {
+    self.result.as_ref().err()?.get(index)
+  }
+}
+
+/// Messages can't contain NUL bytes on the C side, so they end at the first.
+fn c_string(message: &str) -> CString {
+  let message = message.split('\0').next().unwrap_or_default();
+  CString::new(message).expect("the message was cut at the first NUL byte")
+}
+
+fn catch<T>(default: T, f: impl FnOnce() -> T + UnwindSafe) -> T {
+  panic::catch_unwind(f).unwrap_or(default)
+}
+
+fn into_raw(result: FllResult) -> *mut FllResult {
+  Box::into_raw(Box::new(result))
+}
+
+/// Compiles the file at `path`.
+///
+/// # Safety
+///
+/// `path` must be `NULL` or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fll_compile_file(path: *const c_char) -> *mut FllResult {
+  if path.is_null() {
+    return ptr::null_mut();
+  }
+  let path = CStr::from_ptr(path);
+
+  catch(ptr::null_mut(), || {
+    into_raw(match path.to_str() {
+      Ok(path) => FllResult::compile(SourceInput::Path(PathBuf::from(path))),
+      Err(_) => FllResult::error("the path is not valid UTF-8"),
+    })
+  })
+}
+
+/// Compiles `text` as the contents of a source file.
+///
+/// # Safety
+///
+/// `text` must be `NULL` or point to a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn fll_compile_text(text: *const c_char) -> *mut FllResult {
+  if text.is_null() {
+    return ptr::null_mut();
+  }
+  let text = CStr::from_ptr(text);
+
+  catch(ptr::null_mut(), || {
+    into_raw(match text.to_str() {
+      Ok(text) => FllResult::compile(SourceInput::text(text)),
+      Err(_) => FllResult::error("the source text is not valid UTF-8"),
+    })
+  })
+}
+
+/// Whether the compilation succeeded.
+///
+/// # Safety
+///
+/// `result` must be `NULL` or come from `fll_compile_*` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn fll_result_is_ok(result: *const FllResult) -> bool {
+  result.as_ref().is_some_and(|result| result.result.is_ok())
+}
+
+/// The number of tokens produced, 0 if the compilation failed.
+///
+/// # Safety
+///
+/// `result` must be `NULL` or come from `fll_compile_*` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn fll_result_token_count(result: *const FllResult) -> usize {
+  match result.as_ref().map(|result| &result.result) {
+    Some(Ok(artifact)) => artifact.tokens().len(),
+    _ => 0,
+  }
+}
+
+/// The number of diagnostics reported, 0 if the compilation succeeded.
+///
+/// # Safety
+///
+/// `result` must be `NULL` or come from `fll_compile_*` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn fll_result_diagnostic_count(result: *const FllResult) -> usize {
+  match result.as_ref().map(|result| &result.result) {
+    Some(Err(diagnostics)) => diagnostics.len(),
+    _ => 0,
+  }
+}
+
+/// The message of the diagnostic at `index`, or `NULL` if there is none. The
+/// string belongs to `result` and lives until it is freed.
+///
+/// # Safety
+///
+/// `result` must be `NULL` or come from `fll_compile_*` and not be freed.
+#[no_mangle]
+pub unsafe extern "C" fn fll_result_diagnostic_message(
+  result: *const FllResult,
+  index: usize,
+) -> *const c_char {
+  result
+    .as_ref()
+    .and_then(|result| result.diagnostic(index))
+    .map_or(ptr::null(), |diagnostic| diagnostic.message.as_ptr())
+}
+
+/// Writes where the diagnostic at `index` starts to `start`, and where it
+/// ends, right after its last character, to `end`. Returns false and writes
+/// nothing if there is no such diagnostic or it isn't about a part of the
+/// file.
+///
+/// # Safety
+///
+/// `result` must be `NULL` or come from `fll_compile_*` and not be freed.
+/// `start` and `end` must be `NULL` or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn fll_result_diagnostic_range(
+  result: *const FllResult,
+  index: usize,
+  start: *mut FllPosition,
+  end: *mut FllPosition,
+) -> bool {
+  let range = result
+    .as_ref()
+    .and_then(|result| result.diagnostic(index))
+    .and_then(|diagnostic| diagnostic.range.clone());
+
+  match range {
+    Some(range) if !start.is_null() && !end.is_null() => {
+      start.write(range.start.into());
+      end.write(range.end.into());
+      true
+    }
+    _ => false,
+  }
+}
+
+/// Frees a result. Does nothing for `NULL`.
+///
+/// # Safety
+///
+/// `result` must be `NULL` or come from `fll_compile_*` and not be freed
+/// already.
+#[no_mangle]
+pub unsafe extern "C" fn fll_result_free(result: *mut FllResult) {
+  if !result.is_null() {
+    drop(Box::from_raw(result));
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn message(result: *const FllResult, index: usize) -> Option<String> {
+    let message = unsafe { fll_result_diagnostic_message(result, index) };
+    (!message.is_null()).then(|| {
+      unsafe { CStr::from_ptr(message) }
+        .to_string_lossy()
+        .into_owned()
+    })
+  }
+
+  #[test]
+  fn compile_text() {
+    let text = CString::new("main();\n").unwrap();
+
+    unsafe {
+      let result = fll_compile_text(text.as_ptr());
+
+      assert!(fll_result_is_ok(result));
+      assert_eq!(fll_result_token_count(result), 6);
+      assert_eq!(fll_result_diagnostic_count(result), 0);
+      fll_result_free(result);
+    }
+  }
+
+  #[test]
+  fn compile_file_with_errors() {
+    let path = CString::new("test/unhandled_char.fl").unwrap();
+
+    unsafe {
+      let result = fll_compile_file(path.as_ptr());
+
+      assert!(!fll_result_is_ok(result));
+      assert_eq!(fll_result_diagnostic_count(result), 1);
+      assert_eq!(
+        message(result, 0).as_deref(),
+        Some("test/unhandled_char.fl:1:5: unexpected character `$`")
+      );
+      assert_eq!(message(result, 1), None);
+
+      let (mut start, mut end) = (FllPosition::default(), FllPosition::default());
+      assert!(fll_result_diagnostic_range(result, 0, &mut start, &mut end));
+      assert_eq!(start, FllPosition { line: 1, column: 5 });
+      assert_eq!(end, FllPosition { line: 1, column: 6 });
+      assert!(!fll_result_diagnostic_range(
+        result, 1, &mut start, &mut end
+      ));
+      fll_result_free(result);
+    }
+  }
+
+  /// The C spelling of a Rust parameter or return type.
+  fn c_type(rust: &str) -> String {
+    if let Some(pointee) = rust.strip_prefix("*const ") {
+      return format!("const {} *", c_type(pointee));
+    }
+    if let Some(pointee) = rust.strip_prefix("*mut ") {
+      return format!("{} *", c_type(pointee));
+    }
+    match rust {
+      "" => "void",
+      "c_char" => "char",
+      "usize" => "size_t",
+      other => other,
+    }
+    .to_string()
+  }
+
+  /// Joins a C type and a name the way `fll.h` does, with `*` next to the
+  /// name.
+  fn declarator(c_type: &str, name: &str) -> String {
+    if c_type.ends_with('*') {
+      format!("{}{}", c_type, name)
+    } else {
+      format!("{} {}", c_type, name)
+    }
+  }
+
+  /// `fll.h` is written by hand, so every exported function has to be
+  /// declared there with the prototype its Rust signature implies, and
+  /// nothing else may be.
+  #[test]
+  fn header_matches_exports() {
+    let source = include_str!("lib.rs").split("#[cfg(test)]").next().unwrap();
+    let header = include_str!("../include/fll.h")
+      .split_whitespace()
+      .collect::<Vec<_>>()
+      .join(" ");
+
+    let exports: Vec<_> = source
+      .split("extern \"C\" fn ")
+      .skip(1)
+      .map(|function| {
+        let (name, rest) = function.split_once('(').unwrap();
+        let (parameters, rest) = rest.split_once(')').unwrap();
+        let return_type = rest.split_once('{').unwrap().0.trim();
+        let parameters: Vec<_> = parameters
+          .split(',')
+          .map(str::trim)
+          .filter(|parameter| !parameter.is_empty())
+          .map(|parameter| {
+            let (name, rust) = parameter.split_once(": ").unwrap();
+            declarator(&c_type(rust), name)
+          })
+          .collect();
+        let return_type = c_type(return_type.trim_start_matches("->").trim());
+
+        (
+          name,
+          format!(
+            "{}({});",
+            declarator(&return_type, name),
+            parameters.join(", ")
+          ),
+        )
+      })
+      .collect();
+
+    for (name, prototype) in &exports {
+      assert!(
+        header.contains(prototype.as_str()),
+        "{} is missing",
+        prototype
+      );
+      assert!(name.starts_with("fll_"), "{}", name);
+    }
+    let declared: Vec<_> = header
+      .split('(')
+      .filter_map(|before| {
+        before
+          .rsplit(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+          .next()
+      })
+      .filter(|name| name.starts_with("fll_"))
+      .collect();
+    let exported: Vec<_> = exports.iter().map(|(name, _)| *name).collect();
+    assert_eq!(declared, exported);
+  }
+
+  #[test]
+  fn null_arguments() {
+    unsafe {
+      assert!(fll_compile_text(ptr::null()).is_null());
+      assert!(!fll_result_is_ok(ptr::null()));
+      assert_eq!(fll_result_diagnostic_count(ptr::null()), 0);
+      assert!(!fll_result_diagnostic_range(
+        ptr::null(),
+        0,
+        ptr::null_mut(),
+        ptr::null_mut()
+      ));
+      fll_result_free(ptr::null_mut());
+    }
+  }
+}
@@ -0,0 +1,16 @@
+//! The stable interface of `fll`.
+//!
+//! Everything re-exported here follows semver: it only changes in breaking
+//! ways with a new major version, and `tests/api.rs` fails if it does so by
+//! accident. Anything reached through other paths may change at any time.
+//!
+//! `Artifact::module` returns the syntax tree the parser builds, from
+//! `fll::ast`. Its types grow with the language and aren't covered yet.
+
+pub use crate::cancel::{CancellationToken, Cancelled};
+pub use crate::compile::{compile, Artifact, CompileOptions, Diagnostic, Diagnostics, SourceInput};
+pub use crate::error::{FadeError, LexError, LexErrorKind, ParseError, ParseErrorKind};
+pub use crate::observer::{Observer, Phase};
+pub use crate::session::{Session, SessionBuilder};
+pub use crate::token::Token;
+pub use crate::tokenizer::{CaretPos, Span};
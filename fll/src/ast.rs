@@ -0,0 +1,95 @@
+//! The syntax tree the parser builds from a file's tokens.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::token::OperatorType;
+
+/// The items of one source file, in order.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Module {
+  pub items: Vec<Item>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Item {
+  Function(Function),
+}
+
+/// `add(a: u8, b: u8): -> u8 { ... };`, or without a body a declaration,
+/// e.g. of an intrinsic.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Function {
+  pub name: String,
+  pub parameters: Vec<Parameter>,
+  pub return_type: Option<Type>,
+  pub body: Option<Block>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Parameter {
+  pub name: String,
+  pub parameter_type: Type,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Type {
+  /// A built-in type spelled with a keyword, like `u8`.
+  Primitive(String),
+  /// A type referred to by name, like `Number`.
+  Named(String),
+}
+
+impl Type {
+  /// The keywords that name built-in types.
+  pub const PRIMITIVES: &'static [&'static str] = &[
+    "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "f32", "f64", "bool", "string",
+  ];
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Block {
+  pub statements: Vec<Statement>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Statement {
+  Return(Option<Expression>),
+  Expression(Expression),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expression {
+  Identifier(String),
+  String(String),
+  /// `255_u8`, with the value already in base ten and the separators gone.
+  Integer {
+    value: u64,
+    suffix: Option<String>,
+  },
+  Bool(bool),
+  Call {
+    callee: Box<Expression>,
+    arguments: Vec<Expression>,
+  },
+  Unary {
+    operator: OperatorType,
+    operand: Box<Expression>,
+  },
+  /// `<` and `>` are always `LessThan` and `GreaterThan` here, never the
+  /// generic brackets they lex as.
+  Binary {
+    operator: OperatorType,
+    left: Box<Expression>,
+    right: Box<Expression>,
+  },
+}
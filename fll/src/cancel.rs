@@ -0,0 +1,41 @@
+use alloc::sync::Arc;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use thiserror::Error;
+
+/// Lets one thread ask a compilation running on another to stop.
+///
+/// Clones share their state, so cancelling any of them cancels them all. The
+/// compiler checks the token between phases and while lexing, so it stops
+/// soon after, but not immediately.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+  cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn cancel(&self) {
+    self.cancelled.store(true, Ordering::Relaxed);
+  }
+
+  pub fn is_cancelled(&self) -> bool {
+    self.cancelled.load(Ordering::Relaxed)
+  }
+
+  pub fn check(&self) -> Result<(), Cancelled> {
+    if self.is_cancelled() {
+      Err(Cancelled)
+    } else {
+      Ok(())
+    }
+  }
+}
+
+/// The result of a compilation that was cancelled before it finished.
+#[derive(Clone, Copy, Debug, Error, Eq, PartialEq)]
+#[error("compilation was cancelled")]
+pub struct Cancelled;
@@ -0,0 +1,286 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::ast::Module;
+use crate::session::Session;
+use crate::source::Source;
+use crate::token::Token;
+use crate::tokenizer::{CaretPos, Span};
+
+/// The source code to compile, either read from a file or given directly.
+#[derive(Clone, Debug)]
+pub enum SourceInput {
+  Path(PathBuf),
+  Text(String),
+}
+
+impl SourceInput {
+  pub fn path(path: impl Into<PathBuf>) -> Self {
+    Self::Path(path.into())
+  }
+
+  pub fn text(text: impl Into<String>) -> Self {
+    Self::Text(text.into())
+  }
+}
+
+impl From<&Path> for SourceInput {
+  fn from(path: &Path) -> Self {
+    Self::Path(path.to_path_buf())
+  }
+}
+
+impl From<PathBuf> for SourceInput {
+  fn from(path: PathBuf) -> Self {
+    Self::Path(path)
+  }
+}
+
+impl From<String> for SourceInput {
+  fn from(text: String) -> Self {
+    Self::Text(text)
+  }
+}
+
+impl From<&Source> for SourceInput {
+  fn from(source: &Source) -> Self {
+    Self::Path(source.path().to_path_buf())
+  }
+}
+
+/// Settings for `compile`, the ones `SessionBuilder` takes under the same
+/// names.
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct CompileOptions {
+  pub target: Option<String>,
+  /// Clamped to the supported range of 0 to 3.
+  pub opt_level: u8,
+  /// Experimental: lets line breaks end statements where a `;` is missing.
+  pub automatic_semicolons: bool,
+}
+
+/// Everything produced by a successful `compile`.
+#[derive(Debug)]
+pub struct Artifact {
+  tokens: Vec<(Token, Span)>,
+  module: Module,
+}
+
+impl Artifact {
+  pub(crate) fn new(tokens: Vec<(Token, Span)>, module: Module) -> Self {
+    Self { tokens, module }
+  }
+
+  /// The syntax tree of the file.
+  pub fn module(&self) -> &Module {
+    &self.module
+  }
+
+  pub fn tokens(&self) -> &[(Token, Span)] {
+    &self.tokens
+  }
+
+  pub fn into_tokens(self) -> Vec<(Token, Span)> {
+    self.tokens
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+  message: String,
+  #[cfg_attr(
+    feature = "serde",
+    serde(default, skip_serializing_if = "Option::is_none")
+  )]
+  range: Option<Range<CaretPos>>,
+}
+
+impl Diagnostic {
+  pub fn error(message: impl Into<String>) -> Self {
+    Self {
+      message: message.into(),
+      range: None,
+    }
+  }
+
+  /// Points the diagnostic at the source text from `range.start` up to right
+  /// before `range.end`.
+  pub fn with_range(mut self, range: Range<CaretPos>) -> Self {
+    self.range = Some(range);
+    self
+  }
+
+  pub fn message(&self) -> &str {
+    &self.message
+  }
+
+  /// Where in the file the error is, for editors to underline. `None` for
+  /// errors about the file as a whole, like a missing one.
+  pub fn range(&self) -> Option<Range<CaretPos>> {
+    self.range.clone()
+  }
+}
+
+impl Display for Diagnostic {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "error: {}", self.message)
+  }
+}
+
+/// The diagnostics of a failed `compile`, never empty.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Diagnostics(Vec<Diagnostic>);
+
+impl Diagnostics {
+  pub fn iter(&self) -> std::slice::Iter<'_, Diagnostic> {
+    self.0.iter()
+  }
+}
+
+impl From<Diagnostic> for Diagnostics {
+  fn from(diagnostic: Diagnostic) -> Self {
+    Self(vec![diagnostic])
+  }
+}
+
+impl IntoIterator for Diagnostics {
+  type Item = Diagnostic;
+  type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.0.into_iter()
+  }
+}
+
+impl Display for Diagnostics {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    for diagnostic in &self.0 {
+      writeln!(f, "{}", diagnostic)?;
+    }
+    Ok(())
+  }
+}
+
+impl std::error::Error for Diagnostics {}
+
+/// Runs the whole compiler over `input` in one call, with a fresh `Session`
+/// configured by `options`.
+pub fn compile(
+  input: impl Into<SourceInput>,
+  options: CompileOptions,
+) -> Result<Artifact, Diagnostics> {
+  let builder = Session::builder()
+    .opt_level(options.opt_level)
+    .automatic_semicolons(options.automatic_semicolons);
+  let session = match options.target {
+    Some(target) => builder.target(target).build(),
+    None => builder.build(),
+  };
+
+  session.compile(input)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn compile_text() {
+    let artifact = compile(SourceInput::text("main();\n"), CompileOptions::default()).unwrap();
+
+    assert_eq!(artifact.tokens().len(), 6);
+  }
+
+  #[test]
+  fn compile_with_options() {
+    let options = CompileOptions {
+      automatic_semicolons: true,
+      ..CompileOptions::default()
+    };
+
+    assert!(compile(SourceInput::text("main()\n"), CompileOptions::default()).is_err());
+    assert!(compile(SourceInput::text("main()\n"), options).is_ok());
+  }
+
+  #[test]
+  fn compile_file() {
+    let artifact = compile(
+      &Source::from("test/tokenizer/add.fl"),
+      CompileOptions::default(),
+    )
+    .unwrap();
+
+    assert_eq!(artifact.tokens().len(), 35);
+  }
+
+  #[test]
+  fn compile_missing_file() {
+    let diagnostics = compile(
+      SourceInput::path("test/compile/missing.fl"),
+      CompileOptions::default(),
+    )
+    .unwrap_err();
+    let messages: Vec<_> = diagnostics.iter().map(Diagnostic::message).collect();
+
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].starts_with("test/compile/missing.fl: "));
+    assert_eq!(diagnostics.iter().next().unwrap().range(), None);
+  }
+
+  #[test]
+  fn diagnostic_range() {
+    let range = |source: &str| {
+      compile(SourceInput::text(source), CompileOptions::default())
+        .unwrap_err()
+        .iter()
+        .next()
+        .unwrap()
+        .range()
+    };
+
+    assert_eq!(
+      range("main();\nx = \"a\\qb\";\n"),
+      Some(CaretPos::from((2, 7))..CaretPos::from((2, 9)))
+    );
+    assert_eq!(
+      range("main();\nreturned x;\n"),
+      Some(CaretPos::from((2, 10))..CaretPos::from((2, 11)))
+    );
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn serialize_diagnostics() {
+    let diagnostics = Diagnostics::from(Diagnostic::error("x.fl:1:3: unexpected character `=`"));
+    let json = serde_json::to_string(&diagnostics).unwrap();
+
+    assert_eq!(
+      json,
+      r#"[{"message":"x.fl:1:3: unexpected character `=`"}]"#
+    );
+    assert_eq!(
+      serde_json::from_str::<Diagnostics>(&json).unwrap(),
+      diagnostics
+    );
+
+    let diagnostics = Diagnostics::from(
+      Diagnostic::error("x.fl:1:3: unexpected character `=`")
+        .with_range(CaretPos::from((1, 3))..CaretPos::from((1, 4))),
+    );
+    let json = serde_json::to_string(&diagnostics).unwrap();
+
+    assert_eq!(
+      json,
+      r#"[{"message":"x.fl:1:3: unexpected character `=`","range":{"start":{"line":1,"column":3},"end":{"line":1,"column":4}}}]"#
+    );
+    assert_eq!(
+      serde_json::from_str::<Diagnostics>(&json).unwrap(),
+      diagnostics
+    );
+  }
+}
@@ -0,0 +1,105 @@
+//! Generates source text containing every kind of token, so the tests can
+//! check the tokenizer and the token definitions don't drift apart.
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::token::*;
+
+/// Every token the tokenizer can produce, next to the source text it is
+/// lexed from.
+fn tokens() -> Vec<(String, Token)> {
+  let mut tokens: Vec<(String, Token)> = Vec::new();
+
+  for keyword in KEYWORDS {
+    tokens.push((keyword.to_string(), Keyword::from(*keyword).into()));
+  }
+  tokens.push(("name".to_string(), Identifier::from("name").into()));
+  for operator_type in OperatorType::ALL.iter().filter(|o| o.is_lexed()) {
+    tokens.push((
+      operator_type.symbol().to_string(),
+      Operator::from(*operator_type).into(),
+    ));
+  }
+  tokens.push((Parenthesis::OPEN.to_string(), Parenthesis::open().into()));
+  tokens.push((Parenthesis::CLOSE.to_string(), Parenthesis::close().into()));
+  tokens.push((Bracket::OPEN.to_string(), Bracket::open().into()));
+  tokens.push((Bracket::CLOSE.to_string(), Bracket::close().into()));
+  tokens.push((Brace::OPEN.to_string(), Brace::open().into()));
+  tokens.push((Brace::CLOSE.to_string(), Brace::close().into()));
+  tokens.push(("\"a\\tb\"".to_string(), StringLiteral::from("a\tb").into()));
+  tokens.push((
+    "0x1F_u8".to_string(),
+    IntegerLiteral::new(31, Some("u8")).into(),
+  ));
+
+  tokens
+}
+
+/// The source text of every token separated by spaces, and the tokens it
+/// should lex to.
+pub fn snippet() -> (String, Vec<Token>) {
+  let mut source = Vec::new();
+  let mut expected = Vec::new();
+
+  for (text, token) in tokens() {
+    if !source.is_empty() {
+      expected.push(Token::Whitespace);
+    }
+    source.push(text);
+    expected.push(token);
+  }
+  expected.push(Token::NewLine);
+  expected.push(Token::EndOfFile);
+
+  (source.join(" ") + "\n", expected)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::tokenizer::Tokenizer;
+
+  fn lex(source: &str) -> Vec<Token> {
+    Tokenizer::default()
+      .tokenize_str(source)
+      .unwrap_or_else(|err| panic!("`{}` doesn't lex: {:?}", source, err))
+      .into_iter()
+      .map(|(token, _)| token)
+      .collect()
+  }
+
+  #[test]
+  fn every_token() {
+    let (source, expected) = snippet();
+
+    assert_eq!(lex(&source), expected);
+  }
+
+  #[test]
+  fn every_operator() {
+    let mut names: Vec<_> = OperatorType::ALL
+      .iter()
+      .map(|operator_type| format!("{:?}", operator_type))
+      .collect();
+    names.sort();
+    names.dedup();
+    assert_eq!(names.len(), OperatorType::ALL.len(), "duplicate in `ALL`");
+
+    for operator_type in OperatorType::ALL {
+      let symbol = operator_type.symbol();
+      let lexed_as = OperatorType::ALL
+        .iter()
+        .find(|o| o.is_lexed() && o.symbol() == symbol)
+        .unwrap_or_else(|| panic!("nothing lexes `{}`", symbol));
+
+      assert_eq!(
+        lex(symbol),
+        [Operator::from(*lexed_as).into(), Token::EndOfFile],
+        "`{}`",
+        symbol
+      );
+    }
+  }
+}
@@ -0,0 +1,142 @@
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+#[cfg(feature = "std")]
+use alloc::boxed::Box;
+use alloc::string::String;
+use thiserror::Error;
+
+#[cfg(feature = "std")]
+use crate::cancel::Cancelled;
+#[cfg(feature = "std")]
+use crate::compile::Diagnostic;
+use crate::tokenizer::{CaretPos, Span};
+
+/// Everything that can make compiling a source file fail.
+///
+/// Reading, lexing and parsing are the only stages so far, later ones will
+/// add their own variants.
+#[cfg(feature = "std")]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum FadeError {
+  #[error("{}: {source}", path.display())]
+  Io {
+    path: PathBuf,
+    #[source]
+    source: std::io::Error,
+  },
+
+  #[error("{}{}: {source}", path.display(), source.pos)]
+  Lex {
+    path: PathBuf,
+    #[source]
+    source: LexError,
+  },
+
+  #[error("{}{}: {source}", path.display(), source.span.start)]
+  Parse {
+    path: PathBuf,
+    /// Boxed because its span would make every `FadeError` larger.
+    #[source]
+    source: Box<ParseError>,
+  },
+
+  #[error(transparent)]
+  Cancelled(#[from] Cancelled),
+}
+
+/// Source text the tokenizer can't turn into tokens.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[error("{kind}")]
+pub struct LexError {
+  pub kind: LexErrorKind,
+  pub pos: CaretPos,
+  /// How many characters the offending text covers, starting at `pos`. It
+  /// never spans lines.
+  pub width: usize,
+}
+
+impl LexError {
+  /// Where the offending text ends, right after its last character. Editors
+  /// underline from `pos` up to here.
+  pub fn end(&self) -> CaretPos {
+    CaretPos::from((self.pos.get_line(), self.pos.get_column() + self.width))
+  }
+}
+
+#[derive(Clone, Copy, Debug, Error, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum LexErrorKind {
+  /// A character no token starts with.
+  #[error("unexpected character `{0}`")]
+  UnexpectedChar(char),
+
+  /// A string literal without its closing quote, reported at the opening one.
+  #[error("unterminated string literal")]
+  UnterminatedString,
+
+  /// A backslash followed by a character that doesn't form an escape,
+  /// reported at the backslash.
+  #[error("unknown escape sequence `\\{0}`")]
+  UnknownEscape(char),
+
+  /// A digit too large for the base of an integer literal, like the `2` in
+  /// `0b102`.
+  #[error("invalid digit `{digit}` in a base {radix} literal")]
+  InvalidDigit { digit: char, radix: u32 },
+
+  /// A base prefix like `0x` without digits after it, reported at the `0`.
+  #[error("integer literal has no digits")]
+  MissingDigits,
+
+  /// An integer literal above `u64::MAX`, reported at its start.
+  #[error("integer literal is too large")]
+  IntegerTooLarge,
+
+  /// Letters after an integer literal that aren't a type like `u8`, reported
+  /// at the first of them.
+  #[error("invalid suffix for an integer literal")]
+  InvalidSuffix,
+}
+
+/// Tokens that don't form a valid program.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[error("{kind}")]
+pub struct ParseError {
+  pub kind: ParseErrorKind,
+  /// The token the error is about.
+  pub span: Span,
+}
+
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum ParseErrorKind {
+  #[error("expected {expected}, found {found}")]
+  Unexpected { expected: String, found: String },
+
+  /// An identifier one edit away from the keyword that fits where it is.
+  #[error("`{found}` is not a keyword, did you mean `{keyword}`?")]
+  MisspelledKeyword { found: String, keyword: String },
+}
+
+#[cfg(feature = "std")]
+impl From<FadeError> for Diagnostic {
+  fn from(err: FadeError) -> Self {
+    let range = match &err {
+      FadeError::Lex { source, .. } => Some(source.pos..source.end()),
+      FadeError::Parse { source, .. } => Some(source.span.start..source.span.end),
+      _ => None,
+    };
+    let diagnostic = Diagnostic::error(err.to_string());
+
+    match range {
+      Some(range) => diagnostic.with_range(range),
+      None => diagnostic,
+    }
+  }
+}
@@ -0,0 +1,572 @@
+//! The grammar of fadelang as data, for documentation and editor support.
+//!
+//! The lexical rules are built from the definitions in `token` and the
+//! syntactic ones from the operator tables of `parser`. The tests check that
+//! they match everything the tokenizer produces and accept what the parser
+//! accepts, so what `flc grammar` generates from them can't drift from the
+//! implementation.
+//!
+//! The syntactic rules describe sequences of tokens, whitespace and line
+//! breaks can come between any two of them. They leave out the experimental
+//! automatic semicolons.
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::ast::Type;
+use crate::parser::{binary_operator, is_unary_operator};
+use crate::token::{
+  Brace, Bracket, IntegerLiteral, OperatorType, Parenthesis, StringLiteral, KEYWORDS,
+};
+
+/// The rules for binary operators, from the loosest binding to the tightest.
+/// Each one combines operands of the next, the last one those of `unary`.
+const PRECEDENCE_LEVELS: [&str; 9] = [
+  "or",
+  "and",
+  "comparison",
+  "bitor",
+  "bitxor",
+  "bitand",
+  "shift",
+  "sum",
+  "product",
+];
+
+/// The right-hand side of a rule, with the operators of W3C EBNF.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Expression {
+  /// Text matched as it is.
+  Terminal(String),
+  /// The rule with this name.
+  Rule(&'static str),
+  /// One character in one of the inclusive ranges, or with `negated` one
+  /// outside all of them.
+  Class {
+    ranges: Vec<(char, char)>,
+    negated: bool,
+  },
+  Sequence(Vec<Expression>),
+  Choice(Vec<Expression>),
+  Optional(Box<Expression>),
+  ZeroOrMore(Box<Expression>),
+  OneOrMore(Box<Expression>),
+  /// What the first expression matches, unless the second one matches it as
+  /// well.
+  Except(Box<Expression>, Box<Expression>),
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Rule {
+  pub name: &'static str,
+  pub expression: Expression,
+}
+
+/// The rules of the language, the start rule first.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grammar {
+  rules: Vec<Rule>,
+}
+
+impl Grammar {
+  pub fn rules(&self) -> &[Rule] {
+    &self.rules
+  }
+
+  /// The expression of the rule `name`, which has to exist.
+  pub fn rule(&self, name: &str) -> &Expression {
+    self
+      .rules
+      .iter()
+      .find(|rule| rule.name == name)
+      .map(|rule| &rule.expression)
+      .unwrap_or_else(|| panic!("no grammar rule `{}`", name))
+  }
+}
+
+impl Default for Grammar {
+  fn default() -> Self {
+    // Longest first, so that a regular expression built from the rule
+    // doesn't match `>>=` as `>` followed by `>=`.
+    let mut operators: Vec<_> = OperatorType::ALL.iter().map(OperatorType::symbol).collect();
+    operators.sort_by(|a, b| b.len().cmp(&a.len()).then(a.cmp(b)));
+    operators.dedup();
+
+    let escapes = (0..=127u8)
+      .map(char::from)
+      .filter(|c| StringLiteral::unescape(*c).is_some())
+      .map(|c| terminal(c.to_string()))
+      .collect();
+    // Separators may come before the first digit, but not instead of it.
+    let radix_digits = |prefix: char, digits: &[(char, char)]| {
+      let mut ranges = digits.to_vec();
+      ranges.push((IntegerLiteral::SEPARATOR, IntegerLiteral::SEPARATOR));
+      Expression::Sequence(vec![
+        terminal(['0', prefix].iter().collect::<String>()),
+        zero_or_more(terminal(IntegerLiteral::SEPARATOR.to_string())),
+        class(digits, false),
+        zero_or_more(class(&ranges, false)),
+      ])
+    };
+    let quote = || terminal(StringLiteral::QUOTE.to_string());
+    let symbol = |operator_type: OperatorType| terminal(operator_type.symbol());
+    let list = |item: &'static str| {
+      Expression::Sequence(vec![
+        Expression::Rule(item),
+        zero_or_more(Expression::Sequence(vec![
+          symbol(OperatorType::CommaSeparator),
+          Expression::Rule(item),
+        ])),
+        optional(symbol(OperatorType::CommaSeparator)),
+      ])
+    };
+    let parenthesized = |inner: Expression| {
+      Expression::Sequence(vec![
+        terminal(Parenthesis::OPEN.to_string()),
+        inner,
+        terminal(Parenthesis::CLOSE.to_string()),
+      ])
+    };
+
+    let mut rules = vec![
+      rule("module", zero_or_more(Expression::Rule("function"))),
+      rule(
+        "function",
+        Expression::Sequence(vec![
+          Expression::Rule("identifier"),
+          parenthesized(optional(Expression::Rule("parameters"))),
+          optional(Expression::Sequence(vec![
+            symbol(OperatorType::TypeSpecifier),
+            symbol(OperatorType::ReturnType),
+            Expression::Rule("type"),
+          ])),
+          optional(Expression::Rule("block")),
+          symbol(OperatorType::StatementTerminator),
+        ]),
+      ),
+      rule("parameters", list("parameter")),
+      rule(
+        "parameter",
+        Expression::Sequence(vec![
+          Expression::Rule("identifier"),
+          symbol(OperatorType::TypeSpecifier),
+          Expression::Rule("type"),
+        ]),
+      ),
+      rule(
+        "type",
+        Expression::Choice(
+          Type::PRIMITIVES
+            .iter()
+            .map(|primitive| terminal(*primitive))
+            .chain([Expression::Rule("identifier")])
+            .collect(),
+        ),
+      ),
+      rule(
+        "block",
+        Expression::Sequence(vec![
+          terminal(Brace::OPEN.to_string()),
+          zero_or_more(Expression::Rule("statement")),
+          terminal(Brace::CLOSE.to_string()),
+        ]),
+      ),
+      rule(
+        "statement",
+        Expression::Sequence(vec![
+          Expression::Choice(vec![
+            Expression::Sequence(vec![
+              terminal("return"),
+              optional(Expression::Rule("expression")),
+            ]),
+            Expression::Rule("expression"),
+          ]),
+          symbol(OperatorType::StatementTerminator),
+        ]),
+      ),
+      rule("expression", Expression::Rule(PRECEDENCE_LEVELS[0])),
+    ];
+
+    for (level, name) in PRECEDENCE_LEVELS.iter().enumerate() {
+      let operand = PRECEDENCE_LEVELS.get(level + 1).copied().unwrap_or("unary");
+      let mut operators: Vec<_> = OperatorType::ALL
+        .iter()
+        .filter(|operator_type| {
+          binary_operator(**operator_type).map(|(binding, _)| usize::from(binding))
+            == Some(level + 1)
+        })
+        .map(OperatorType::symbol)
+        .collect();
+      operators.dedup();
+      let operator = match operators[..] {
+        [operator] => terminal(operator),
+        _ => Expression::Choice(operators.into_iter().map(terminal).collect()),
+      };
+
+      rules.push(rule(
+        name,
+        Expression::Sequence(vec![
+          Expression::Rule(operand),
+          zero_or_more(Expression::Sequence(vec![
+            operator,
+            Expression::Rule(operand),
+          ])),
+        ]),
+      ));
+    }
+
+    rules.extend([
+      rule(
+        "unary",
+        Expression::Choice(vec![
+          Expression::Sequence(vec![
+            Expression::Choice(
+              OperatorType::ALL
+                .iter()
+                .filter(|operator_type| is_unary_operator(**operator_type))
+                .map(|operator_type| symbol(*operator_type))
+                .collect(),
+            ),
+            Expression::Rule("unary"),
+          ]),
+          Expression::Rule("call"),
+        ]),
+      ),
+      rule(
+        "call",
+        Expression::Sequence(vec![
+          Expression::Rule("primary"),
+          zero_or_more(parenthesized(optional(Expression::Rule("arguments")))),
+        ]),
+      ),
+      rule("arguments", list("expression")),
+      rule(
+        "primary",
+        Expression::Choice(vec![
+          Expression::Rule("identifier"),
+          Expression::Rule("string"),
+          Expression::Rule("integer"),
+          terminal("true"),
+          terminal("false"),
+          parenthesized(Expression::Rule("expression")),
+        ]),
+      ),
+      rule(
+        "token",
+        Expression::Choice(
+          [
+            "keyword",
+            "identifier",
+            "operator",
+            "bracket",
+            "string",
+            "integer",
+            "whitespace",
+            "newline",
+          ]
+          .into_iter()
+          .map(Expression::Rule)
+          .collect(),
+        ),
+      ),
+      rule(
+        "keyword",
+        Expression::Choice(KEYWORDS.iter().map(|keyword| terminal(*keyword)).collect()),
+      ),
+      rule(
+        "identifier",
+        Expression::Except(
+          Box::new(Expression::Sequence(vec![
+            class(&[('a', 'z'), ('A', 'Z'), ('_', '_')], false),
+            zero_or_more(class(
+              &[('a', 'z'), ('A', 'Z'), ('0', '9'), ('_', '_')],
+              false,
+            )),
+          ])),
+          Box::new(Expression::Rule("keyword")),
+        ),
+      ),
+      rule(
+        "operator",
+        Expression::Choice(operators.into_iter().map(terminal).collect()),
+      ),
+      // Every opening bracket is followed by its closing one, editors pair
+      // them up by that.
+      rule(
+        "bracket",
+        Expression::Choice(
+          [
+            Parenthesis::OPEN,
+            Parenthesis::CLOSE,
+            Bracket::OPEN,
+            Bracket::CLOSE,
+            Brace::OPEN,
+            Brace::CLOSE,
+          ]
+          .into_iter()
+          .map(|c| terminal(c.to_string()))
+          .collect(),
+        ),
+      ),
+      rule(
+        "string",
+        Expression::Sequence(vec![
+          quote(),
+          zero_or_more(Expression::Choice(vec![
+            class(
+              &[
+                (StringLiteral::QUOTE, StringLiteral::QUOTE),
+                (StringLiteral::ESCAPE, StringLiteral::ESCAPE),
+              ],
+              true,
+            ),
+            Expression::Rule("escape"),
+          ])),
+          quote(),
+        ]),
+      ),
+      rule(
+        "escape",
+        Expression::Sequence(vec![
+          terminal(StringLiteral::ESCAPE.to_string()),
+          Expression::Choice(escapes),
+        ]),
+      ),
+      rule(
+        "integer",
+        Expression::Sequence(vec![
+          Expression::Choice(vec![
+            Expression::Sequence(vec![
+              class(&[('0', '9')], false),
+              zero_or_more(class(&[('0', '9'), ('_', '_')], false)),
+            ]),
+            radix_digits('x', &[('0', '9'), ('a', 'f'), ('A', 'F')]),
+            radix_digits('o', &[('0', '7')]),
+            radix_digits('b', &[('0', '1')]),
+          ]),
+          optional(Expression::Choice(
+            IntegerLiteral::SUFFIXES
+              .iter()
+              .map(|suffix| terminal(*suffix))
+              .collect(),
+          )),
+        ]),
+      ),
+      rule("whitespace", one_or_more(terminal(" "))),
+      rule("newline", terminal("\n")),
+    ]);
+
+    Self { rules }
+  }
+}
+
+fn rule(name: &'static str, expression: Expression) -> Rule {
+  Rule { name, expression }
+}
+
+fn terminal(text: impl Into<String>) -> Expression {
+  Expression::Terminal(text.into())
+}
+
+fn class(ranges: &[(char, char)], negated: bool) -> Expression {
+  Expression::Class {
+    ranges: ranges.to_vec(),
+    negated,
+  }
+}
+
+fn optional(expression: Expression) -> Expression {
+  Expression::Optional(Box::new(expression))
+}
+
+fn zero_or_more(expression: Expression) -> Expression {
+  Expression::ZeroOrMore(Box::new(expression))
+}
+
+fn one_or_more(expression: Expression) -> Expression {
+  Expression::OneOrMore(Box::new(expression))
+}
+
+#[cfg(test)]
+mod tests {
+  use alloc::collections::BTreeSet;
+
+  use super::*;
+  use crate::coverage;
+  use crate::parser::Parser;
+  use crate::token::Token;
+  use crate::tokenizer::Tokenizer;
+
+  /// The positions in `text` where a match of `expression` starting at
+  /// `start` can end.
+  fn ends(grammar: &Grammar, expression: &Expression, text: &str, start: usize) -> BTreeSet<usize> {
+    let repeat = |inner: &Expression, mut found: BTreeSet<usize>| {
+      let mut todo: Vec<_> = found.iter().copied().collect();
+      while let Some(end) = todo.pop() {
+        for next in ends(grammar, inner, text, end) {
+          if found.insert(next) {
+            todo.push(next);
+          }
+        }
+      }
+      found
+    };
+
+    match expression {
+      Expression::Terminal(terminal) => text[start..]
+        .starts_with(terminal.as_str())
+        .then_some(start + terminal.len())
+        .into_iter()
+        .collect(),
+      Expression::Rule(name) => ends(grammar, grammar.rule(name), text, start),
+      Expression::Class { ranges, negated } => text[start..]
+        .chars()
+        .next()
+        .filter(|c| ranges.iter().any(|(low, high)| (low..=high).contains(&c)) != *negated)
+        .map(|c| start + c.len_utf8())
+        .into_iter()
+        .collect(),
+      Expression::Sequence(items) => items.iter().fold([start].into(), |found, item| {
+        found
+          .into_iter()
+          .flat_map(|end| ends(grammar, item, text, end))
+          .collect()
+      }),
+      Expression::Choice(alternatives) => alternatives
+        .iter()
+        .flat_map(|alternative| ends(grammar, alternative, text, start))
+        .collect(),
+      Expression::Optional(inner) => {
+        let mut found = ends(grammar, inner, text, start);
+        found.insert(start);
+        found
+      }
+      Expression::ZeroOrMore(inner) => repeat(inner, [start].into()),
+      Expression::OneOrMore(inner) => repeat(inner, ends(grammar, inner, text, start)),
+      Expression::Except(inner, except) => ends(grammar, inner, text, start)
+        .into_iter()
+        .filter(|end| !matches(grammar, except, &text[start..*end]))
+        .collect(),
+    }
+  }
+
+  fn matches(grammar: &Grammar, expression: &Expression, text: &str) -> bool {
+    ends(grammar, expression, text, 0).contains(&text.len())
+  }
+
+  #[test]
+  fn rules_are_defined() {
+    fn references(expression: &Expression, found: &mut Vec<&'static str>) {
+      match expression {
+        Expression::Rule(name) => found.push(name),
+        Expression::Terminal(_) | Expression::Class { .. } => {}
+        Expression::Sequence(items) | Expression::Choice(items) => {
+          items.iter().for_each(|item| references(item, found))
+        }
+        Expression::Optional(inner)
+        | Expression::ZeroOrMore(inner)
+        | Expression::OneOrMore(inner) => references(inner, found),
+        Expression::Except(inner, except) => {
+          references(inner, found);
+          references(except, found);
+        }
+      }
+    }
+
+    let grammar = Grammar::default();
+    let mut found = Vec::new();
+    for rule in grammar.rules() {
+      references(&rule.expression, &mut found);
+    }
+    for name in found {
+      grammar.rule(name);
+    }
+  }
+
+  #[test]
+  fn every_token_matches() {
+    let grammar = Grammar::default();
+    let (source, _) = coverage::snippet();
+    let tokens = Tokenizer::default().tokenize_str(&source).unwrap();
+
+    for (token, span) in tokens
+      .iter()
+      .filter(|(token, _)| *token != Token::EndOfFile)
+    {
+      let text = &source[span.byte_range.clone()];
+
+      assert!(
+        matches(&grammar, grammar.rule("token"), text),
+        "`{}` lexes as {:?} but isn't a `token`",
+        text,
+        token
+      );
+    }
+  }
+
+  #[test]
+  fn syntax_matches_the_parser() {
+    let grammar = Grammar::default();
+    let parses = |source: &str| {
+      let tokens = Tokenizer::default().tokenize_str(source).unwrap();
+      Parser::new(&tokens).parse_module().is_ok()
+    };
+
+    // Without whitespace, so that the matcher can work on the text.
+    for source in [
+      "",
+      "main();",
+      "add(a:u8,b:u8,):->u8{return(a+b*c);};",
+      "f(){print(\"hi\",g(x)(y),);return;};",
+      "f(){return!a||b<c&&-d!=e%2<<1;};",
+      "g(p:Point){(p);true;};",
+    ] {
+      assert!(parses(source), "the parser rejects `{}`", source);
+      assert!(
+        matches(&grammar, grammar.rule("module"), source),
+        "`{}` isn't a `module`",
+        source
+      );
+    }
+    for source in ["f()", "f(){x};", "f(a){};", "f(){return(a;};", "f(){a+;};"] {
+      assert!(!parses(source), "the parser accepts `{}`", source);
+      assert!(
+        !matches(&grammar, grammar.rule("module"), source),
+        "`{}` is a `module`",
+        source
+      );
+    }
+  }
+
+  #[test]
+  fn keywords_are_not_identifiers() {
+    let grammar = Grammar::default();
+
+    assert!(matches(&grammar, grammar.rule("identifier"), "returned"));
+    assert!(!matches(&grammar, grammar.rule("identifier"), "return"));
+  }
+
+  #[test]
+  fn integers_match_the_tokenizer() {
+    let grammar = Grammar::default();
+
+    for source in ["0x1F_u8", "0x_1", "0b_1_0", "1_000"] {
+      assert!(Tokenizer::default().tokenize_str(source).is_ok());
+      assert!(
+        matches(&grammar, grammar.rule("integer"), source),
+        "{}",
+        source
+      );
+    }
+    for source in ["0x", "0x_", "0o__"] {
+      assert!(Tokenizer::default().tokenize_str(source).is_err());
+      assert!(
+        !matches(&grammar, grammar.rule("integer"), source),
+        "{}",
+        source
+      );
+    }
+  }
+}
@@ -1,5 +1,47 @@
 #![warn(clippy::all)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
+//! The fadelang front-end.
+//!
+//! Without the default `std` feature only the tokenizer and the parser are
+//! built, on top of `core` and `alloc`. Reading files, sessions and `compile`
+//! need `std`.
+//!
+//! Embedders should stick to `fll::api`, the rest is only public for the
+//! other fadelang crates.
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod api;
+pub mod ast;
+pub mod cancel;
+#[cfg(test)]
+mod coverage;
+#[cfg(feature = "std")]
+pub mod compile;
+pub mod error;
+pub mod grammar;
+pub mod mangle;
+#[cfg(feature = "std")]
+pub mod observer;
+#[doc(hidden)]
+pub mod parser;
+#[cfg(feature = "std")]
+pub mod session;
+#[cfg(feature = "std")]
+#[doc(hidden)]
 pub mod source;
 pub mod token;
+#[doc(hidden)]
 pub mod tokenizer;
+
+pub use cancel::{CancellationToken, Cancelled};
+#[cfg(feature = "std")]
+pub use compile::{compile, CompileOptions, SourceInput};
+#[cfg(feature = "std")]
+pub use error::FadeError;
+#[cfg(feature = "std")]
+pub use observer::{Observer, Phase};
+#[cfg(feature = "std")]
+pub use session::Session;
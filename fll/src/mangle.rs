@@ -0,0 +1,219 @@
+//! Symbol names for native output.
+//!
+//! `math::min<u8>` is mangled as `_FN4math3minIN2u8EEE`. `_F` marks a
+//! fadelang symbol. `N ... E` holds the path, each segment prefixed with its
+//! length in bytes. Generic arguments follow in `I ... E` and are mangled the
+//! same way, without the prefix. The result only contains `[A-Za-z0-9_]`,
+//! which every linker accepts, and stays the same between compiler versions.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+use core::fmt::{Display, Formatter};
+
+pub const PREFIX: &str = "_F";
+
+/// A function or one of its monomorphizations, by path.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Symbol {
+  pub path: Vec<String>,
+  pub generics: Vec<Symbol>,
+}
+
+impl Symbol {
+  pub fn new<S: Into<String>>(path: impl IntoIterator<Item = S>) -> Self {
+    Self {
+      path: path.into_iter().map(Into::into).collect(),
+      generics: Vec::new(),
+    }
+  }
+
+  pub fn with_generics(mut self, generics: impl IntoIterator<Item = Symbol>) -> Self {
+    self.generics = generics.into_iter().collect();
+    self
+  }
+
+  pub fn mangle(&self) -> String {
+    let mut mangled = PREFIX.to_string();
+    self.mangle_into(&mut mangled);
+    mangled
+  }
+
+  fn mangle_into(&self, mangled: &mut String) {
+    mangled.push('N');
+    for segment in &self.path {
+      // Writing to a `String` can't fail.
+      let _ = write!(mangled, "{}{}", segment.len(), segment);
+    }
+    if !self.generics.is_empty() {
+      mangled.push('I');
+      for generic in &self.generics {
+        generic.mangle_into(mangled);
+      }
+      mangled.push('E');
+    }
+    mangled.push('E');
+  }
+
+  /// The symbol `mangled` stands for, `None` if it isn't exactly one mangled
+  /// symbol.
+  pub fn demangle(mangled: &str) -> Option<Self> {
+    match Self::demangle_prefix(mangled) {
+      Some((symbol, "")) => Some(symbol),
+      _ => None,
+    }
+  }
+
+  /// Demangles the symbol at the start of `text` and returns it along with
+  /// the rest of `text`.
+  fn demangle_prefix(text: &str) -> Option<(Self, &str)> {
+    Self::demangle_unprefixed(text.strip_prefix(PREFIX)?)
+  }
+
+  fn demangle_unprefixed(text: &str) -> Option<(Self, &str)> {
+    let mut rest = text.strip_prefix('N')?;
+    let mut path = Vec::new();
+
+    while rest.starts_with(|c: char| c.is_ascii_digit()) {
+      let digits = rest
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(rest.len());
+      let len: usize = rest[..digits].parse().ok()?;
+      rest = &rest[digits..];
+      if len == 0 || rest.len() < len || !rest.is_char_boundary(len) {
+        return None;
+      }
+      path.push(rest[..len].to_string());
+      rest = &rest[len..];
+    }
+    if path.is_empty() {
+      return None;
+    }
+
+    let mut generics = Vec::new();
+    if let Some(args) = rest.strip_prefix('I') {
+      rest = args;
+      while !rest.starts_with('E') {
+        let (generic, after) = Self::demangle_unprefixed(rest)?;
+        generics.push(generic);
+        rest = after;
+      }
+      rest = &rest[1..];
+      if generics.is_empty() {
+        return None;
+      }
+    }
+
+    Some((Self { path, generics }, rest.strip_prefix('E')?))
+  }
+}
+
+impl Display for Symbol {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    write!(f, "{}", self.path.join("::"))?;
+    if let Some((first, rest)) = self.generics.split_first() {
+      write!(f, "<{}", first)?;
+      for generic in rest {
+        write!(f, ", {}", generic)?;
+      }
+      write!(f, ">")?;
+    }
+    Ok(())
+  }
+}
+
+/// Replaces every mangled symbol in `text`, such as a stack trace or a linker
+/// error, with its demangled form. Everything else is kept as is.
+pub fn demangle_all(text: &str) -> String {
+  let mut demangled = String::with_capacity(text.len());
+  let mut rest = text;
+
+  while let Some(start) = rest.find(PREFIX) {
+    let (before, candidate) = rest.split_at(start);
+    demangled.push_str(before);
+
+    // A prefix in the middle of a word isn't the start of a symbol.
+    let in_word = demangled.ends_with(|c: char| c.is_ascii_alphanumeric() || c == '_');
+    match Symbol::demangle_prefix(candidate) {
+      Some((symbol, after)) if !in_word => {
+        let _ = write!(demangled, "{}", symbol);
+        rest = after;
+      }
+      _ => {
+        demangled.push_str(PREFIX);
+        rest = &candidate[PREFIX.len()..];
+      }
+    }
+  }
+
+  demangled.push_str(rest);
+  demangled
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use alloc::vec;
+
+  fn min_u8() -> Symbol {
+    Symbol::new(["math", "min"]).with_generics([Symbol::new(["u8"])])
+  }
+
+  #[test]
+  fn mangle() {
+    assert_eq!(Symbol::new(["main"]).mangle(), "_FN4mainE");
+    assert_eq!(min_u8().mangle(), "_FN4math3minIN2u8EEE");
+  }
+
+  #[test]
+  fn round_trip() {
+    let symbols = [
+      Symbol::new(["main"]),
+      min_u8(),
+      Symbol::new(["res", "Result", "to"]).with_generics([
+        Symbol::new(["res", "Optional"]).with_generics([Symbol::new(["i64"])]),
+        Symbol::new(["string"]),
+      ]),
+    ];
+
+    for symbol in symbols {
+      assert_eq!(Symbol::demangle(&symbol.mangle()), Some(symbol));
+    }
+  }
+
+  #[test]
+  fn display() {
+    let symbol = Symbol::new(["convert"]).with_generics(vec![min_u8(), Symbol::new(["bool"])]);
+
+    assert_eq!(symbol.to_string(), "convert<math::min<u8>, bool>");
+  }
+
+  #[test]
+  fn reject_invalid() {
+    for mangled in [
+      "",
+      "main",
+      "_FN4mainE_",
+      "_FN4main",
+      "_FN5mainE",
+      "_FNE",
+      "_FN0E",
+      "_FN4mainIEE",
+      "_ZN4main17h0123456789abcdefE",
+    ] {
+      assert_eq!(Symbol::demangle(mangled), None, "{}", mangled);
+    }
+  }
+
+  #[test]
+  fn demangle_text() {
+    assert_eq!(
+      demangle_all("at _FN4math3minIN2u8EEE+0x1c (called from _FN4mainE)"),
+      "at math::min<u8>+0x1c (called from main)"
+    );
+    assert_eq!(
+      demangle_all("a_FN4mainE _FN9truncated _F"),
+      "a_FN4mainE _FN9truncated _F"
+    );
+  }
+}
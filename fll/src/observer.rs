@@ -0,0 +1,51 @@
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::ast::Module;
+use crate::compile::Diagnostic;
+use crate::token::Token;
+use crate::tokenizer::Span;
+
+/// A stage of the compiler a file goes through.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Phase {
+  /// Reading the source text, from memory or from disk.
+  Read,
+  Lex,
+  Parse,
+}
+
+impl Phase {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Phase::Read => "read",
+      Phase::Lex => "lex",
+      Phase::Parse => "parse",
+    }
+  }
+}
+
+impl Display for Phase {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+/// Gets notified about the progress of every compilation in a `Session`.
+///
+/// All methods do nothing by default, so implementations only override what
+/// they are interested in. A session may compile several files at once, so
+/// the calls for different files can interleave.
+pub trait Observer: Send + Sync {
+  fn on_phase_start(&self, _phase: Phase, _path: &Path) {}
+
+  fn on_phase_finish(&self, _phase: Phase, _path: &Path, _elapsed: Duration) {}
+
+  fn on_file_lexed(&self, _path: &Path, _tokens: &[(Token, Span)]) {}
+
+  fn on_file_parsed(&self, _path: &Path, _module: &Module) {}
+
+  fn on_diagnostic(&self, _path: &Path, _diagnostic: &Diagnostic) {}
+}
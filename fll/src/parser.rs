@@ -0,0 +1,711 @@
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::ast::*;
+use crate::error::{ParseError, ParseErrorKind};
+use crate::token::*;
+use crate::tokenizer::Span;
+
+/// Which pair of brackets a bracket token belongs to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Delimiter {
+  Parenthesis,
+  Bracket,
+  Brace,
+}
+
+impl Delimiter {
+  fn symbols(&self) -> (char, char) {
+    match self {
+      Delimiter::Parenthesis => (Parenthesis::OPEN, Parenthesis::CLOSE),
+      Delimiter::Bracket => (Bracket::OPEN, Bracket::CLOSE),
+      Delimiter::Brace => (Brace::OPEN, Brace::CLOSE),
+    }
+  }
+
+  fn token(&self, bracket_type: BracketType) -> Token {
+    match self {
+      Delimiter::Parenthesis => Parenthesis::from(bracket_type).into(),
+      Delimiter::Bracket => Bracket::from(bracket_type).into(),
+      Delimiter::Brace => Brace::from(bracket_type).into(),
+    }
+  }
+
+  fn open(&self) -> Token {
+    self.token(BracketType::Opening)
+  }
+
+  fn close(&self) -> Token {
+    self.token(BracketType::Closing)
+  }
+}
+
+/// How `token` is called in "expected ..., found ..." errors.
+fn describe(token: &Token) -> String {
+  match token {
+    Token::Keyword(keyword) => format!("keyword `{}`", keyword.keyword()),
+    Token::Identifier(identifier) => format!("identifier `{}`", identifier.identifier()),
+    Token::StringLiteral(_) => "string literal".to_string(),
+    Token::IntegerLiteral(_) => "integer literal".to_string(),
+    Token::Operator(operator) => format!("`{}`", operator.operator_type().symbol()),
+    Token::Parenthesis(parenthesis) => bracket(Delimiter::Parenthesis, parenthesis.bracket_type()),
+    Token::Bracket(bracket_token) => bracket(Delimiter::Bracket, bracket_token.bracket_type()),
+    Token::Brace(brace) => bracket(Delimiter::Brace, brace.bracket_type()),
+    Token::Whitespace => "whitespace".to_string(),
+    Token::NewLine => "line break".to_string(),
+    Token::EndOfFile => "end of file".to_string(),
+  }
+}
+
+fn bracket(delimiter: Delimiter, bracket_type: BracketType) -> String {
+  let (open, close) = delimiter.symbols();
+  match bracket_type {
+    BracketType::Opening => format!("`{}`", open),
+    BracketType::Closing => format!("`{}`", close),
+  }
+}
+
+fn operator(operator_type: OperatorType) -> Token {
+  Operator::from(operator_type).into()
+}
+
+/// The binding power of a binary operator, higher binds tighter, and the
+/// operator it stands for in an expression.
+pub(crate) fn binary_operator(operator_type: OperatorType) -> Option<(u8, OperatorType)> {
+  let binding = match operator_type {
+    OperatorType::LogicalOr => 1,
+    OperatorType::LogicalAnd => 2,
+    OperatorType::Equals
+    | OperatorType::NotEquals
+    | OperatorType::LessThanOrEqual
+    | OperatorType::GreaterThanOrEqual => 3,
+    OperatorType::GenericBlockBegin => return Some((3, OperatorType::LessThan)),
+    OperatorType::GenericBlockEnd => return Some((3, OperatorType::GreaterThan)),
+    OperatorType::BitwiseOr => 4,
+    OperatorType::BitwiseXOr => 5,
+    OperatorType::BitwiseAnd => 6,
+    OperatorType::BitwiseLeftShift | OperatorType::BitwiseRightShift => 7,
+    OperatorType::Addition | OperatorType::Subtraction => 8,
+    OperatorType::Multiplication | OperatorType::Division | OperatorType::Modulo => 9,
+    _ => return None,
+  };
+  Some((binding, operator_type))
+}
+
+pub(crate) fn is_unary_operator(operator_type: OperatorType) -> bool {
+  matches!(
+    operator_type,
+    OperatorType::LogicalNot | OperatorType::Subtraction | OperatorType::BitwiseNot
+  )
+}
+
+pub struct Parser {
+  tokens: Vec<(Token, Span)>,
+  pos: usize,
+  automatic_semicolons: bool,
+}
+
+impl Parser {
+  pub fn new(tokens: &[(Token, Span)]) -> Self {
+    // The parser never looks at whitespace.
+    let mut tokens: Vec<_> = tokens
+      .iter()
+      .filter(|(token, _)| *token != Token::Whitespace)
+      .cloned()
+      .collect();
+    if tokens.last().map(|(token, _)| token) != Some(&Token::EndOfFile) {
+      let end = tokens.last().map_or_else(Span::default, |(_, span)| Span {
+        start: span.end,
+        end: span.end,
+        byte_range: span.byte_range.end..span.byte_range.end,
+      });
+      tokens.push((Token::EndOfFile, end));
+    }
+
+    Self {
+      tokens,
+      pos: 0,
+      automatic_semicolons: false,
+    }
+  }
+
+  /// Experimental: lets a line break stand in for the `;` ending a statement
+  /// or an item. A `;` is also implied before a `}` and at the end of the
+  /// file.
+  ///
+  /// A line only continues on the next one where it can't end, i.e. after an
+  /// operator, a comma or an opening bracket, or when the next line starts
+  /// with a binary operator. `-` counts as one, so a line starting with `-x`
+  /// subtracts from the line before it. A `(` at the start of a line always
+  /// starts a new statement rather than calling the line before it.
+  pub fn automatic_semicolons(mut self, automatic_semicolons: bool) -> Self {
+    self.automatic_semicolons = automatic_semicolons;
+    self
+  }
+
+  pub fn parse_module(&mut self) -> Result<Module, ParseError> {
+    let mut items = Vec::new();
+
+    while *self.peek() != Token::EndOfFile {
+      items.push(Item::Function(self.function()?));
+    }
+    Ok(Module { items })
+  }
+
+  fn function(&mut self) -> Result<Function, ParseError> {
+    let name = self.identifier("a function name")?;
+
+    self.expect(&Delimiter::Parenthesis.open())?;
+    let mut parameters = Vec::new();
+    while !self.eat(&Delimiter::Parenthesis.close()) {
+      let name = self.identifier("a parameter name")?;
+      self.expect(&operator(OperatorType::TypeSpecifier))?;
+      parameters.push(Parameter {
+        name,
+        parameter_type: self.parameter_type()?,
+      });
+      self.list_separator(Delimiter::Parenthesis)?;
+    }
+
+    let return_type = if self.eat(&operator(OperatorType::TypeSpecifier)) {
+      self.expect(&operator(OperatorType::ReturnType))?;
+      Some(self.parameter_type()?)
+    } else {
+      None
+    };
+
+    let body = if *self.peek() == Delimiter::Brace.open() {
+      Some(self.block()?)
+    } else {
+      None
+    };
+    self.terminator()?;
+
+    Ok(Function {
+      name,
+      parameters,
+      return_type,
+      body,
+    })
+  }
+
+  fn parameter_type(&mut self) -> Result<Type, ParseError> {
+    let parameter_type = match self.peek() {
+      Token::Keyword(keyword) if Type::PRIMITIVES.contains(&keyword.keyword().as_str()) => {
+        Type::Primitive(keyword.keyword())
+      }
+      Token::Identifier(identifier) => Type::Named(identifier.identifier()),
+      _ => return Err(self.unexpected("a type")),
+    };
+    self.advance();
+    Ok(parameter_type)
+  }
+
+  fn block(&mut self) -> Result<Block, ParseError> {
+    self.expect(&Delimiter::Brace.open())?;
+    let mut statements = Vec::new();
+
+    while !self.eat(&Delimiter::Brace.close()) {
+      statements.push(self.statement()?);
+    }
+    Ok(Block { statements })
+  }
+
+  fn statement(&mut self) -> Result<Statement, ParseError> {
+    if self.eat(&Keyword::from("return").into()) {
+      let value = if *self.peek() == operator(OperatorType::StatementTerminator)
+        || self.at_implied_terminator()
+      {
+        None
+      } else {
+        Some(self.expression(0)?)
+      };
+      self.terminator()?;
+      return Ok(Statement::Return(value));
+    }
+
+    // Two operands in a row never form an expression, so `retrun a` is a
+    // misspelled keyword rather than a missing operator.
+    if let Token::Identifier(identifier) = self.peek() {
+      let identifier = identifier.identifier();
+      let starts_operand = matches!(
+        self.peek_nth(1),
+        Token::Identifier(_) | Token::StringLiteral(_) | Token::IntegerLiteral(_)
+      );
+      if let Some(keyword) = similar_keyword(&identifier).filter(|_| starts_operand) {
+        return Err(ParseError {
+          kind: ParseErrorKind::MisspelledKeyword {
+            found: identifier,
+            keyword: keyword.to_string(),
+          },
+          span: self.span(),
+        });
+      }
+    }
+
+    let expression = self.expression(0)?;
+    self.terminator()?;
+    Ok(Statement::Expression(expression))
+  }
+
+  /// Parses operators binding tighter than `min_binding` by precedence
+  /// climbing, all of them left associative.
+  fn expression(&mut self, min_binding: u8) -> Result<Expression, ParseError> {
+    let mut left = self.unary()?;
+
+    while let Token::Operator(operator) = self.peek() {
+      let (binding, operator) = match binary_operator(operator.operator_type()) {
+        Some((binding, operator)) if binding > min_binding => (binding, operator),
+        _ => break,
+      };
+      self.advance();
+
+      let right = self.expression(binding)?;
+      left = Expression::Binary {
+        operator,
+        left: Box::new(left),
+        right: Box::new(right),
+      };
+    }
+    Ok(left)
+  }
+
+  fn unary(&mut self) -> Result<Expression, ParseError> {
+    match self.peek() {
+      Token::Operator(operator) if is_unary_operator(operator.operator_type()) => {
+        let operator = operator.operator_type();
+        self.advance();
+        Ok(Expression::Unary {
+          operator,
+          operand: Box::new(self.unary()?),
+        })
+      }
+      _ => self.call(),
+    }
+  }
+
+  fn call(&mut self) -> Result<Expression, ParseError> {
+    let mut expression = self.primary()?;
+
+    while !(self.automatic_semicolons && self.at_line_break())
+      && self.eat(&Delimiter::Parenthesis.open())
+    {
+      let mut arguments = Vec::new();
+      while !self.eat(&Delimiter::Parenthesis.close()) {
+        arguments.push(self.expression(0)?);
+        self.list_separator(Delimiter::Parenthesis)?;
+      }
+      expression = Expression::Call {
+        callee: Box::new(expression),
+        arguments,
+      };
+    }
+    Ok(expression)
+  }
+
+  fn primary(&mut self) -> Result<Expression, ParseError> {
+    let expression = match self.peek() {
+      Token::Identifier(identifier) => Expression::Identifier(identifier.identifier()),
+      Token::StringLiteral(literal) => Expression::String(literal.value()),
+      Token::IntegerLiteral(literal) => Expression::Integer {
+        value: literal.value(),
+        suffix: literal.suffix(),
+      },
+      Token::Keyword(keyword) if keyword.keyword() == "true" => Expression::Bool(true),
+      Token::Keyword(keyword) if keyword.keyword() == "false" => Expression::Bool(false),
+      Token::Parenthesis(parenthesis) if parenthesis.bracket_type() == BracketType::Opening => {
+        self.advance();
+        let expression = self.expression(0)?;
+        self.expect(&Delimiter::Parenthesis.close())?;
+        return Ok(expression);
+      }
+      _ => return Err(self.unexpected("an expression")),
+    };
+    self.advance();
+    Ok(expression)
+  }
+
+  /// After an element of a list, expects a comma or the end of the list.
+  /// The closing bracket is left for the caller, a trailing comma is fine.
+  fn list_separator(&mut self, delimiter: Delimiter) -> Result<(), ParseError> {
+    if self.eat(&operator(OperatorType::CommaSeparator)) || *self.peek() == delimiter.close() {
+      Ok(())
+    } else {
+      Err(self.unexpected(&format!("`,` or `{}`", delimiter.symbols().1)))
+    }
+  }
+
+  /// Expects the `;` after a statement or an item, unless it is implied.
+  fn terminator(&mut self) -> Result<(), ParseError> {
+    if self.eat(&operator(OperatorType::StatementTerminator)) || self.at_implied_terminator() {
+      Ok(())
+    } else {
+      Err(self.unexpected("`;`"))
+    }
+  }
+
+  /// Whether automatic mode ends the statement here without a `;`. A `;`
+  /// starting the next line still belongs to the statement before it.
+  fn at_implied_terminator(&self) -> bool {
+    self.automatic_semicolons
+      && match self.peek() {
+        Token::Operator(operator)
+          if operator.operator_type() == OperatorType::StatementTerminator =>
+        {
+          false
+        }
+        Token::Brace(brace) if brace.bracket_type() == BracketType::Closing => true,
+        Token::EndOfFile => true,
+        _ => self.at_line_break(),
+      }
+  }
+
+  /// Whether a line break comes before the next token.
+  fn at_line_break(&self) -> bool {
+    self.tokens[self.pos].0 == Token::NewLine
+  }
+
+  fn identifier(&mut self, expected: &str) -> Result<String, ParseError> {
+    match self.peek() {
+      Token::Identifier(identifier) => {
+        let identifier = identifier.identifier();
+        self.advance();
+        Ok(identifier)
+      }
+      _ => Err(self.unexpected(expected)),
+    }
+  }
+
+  fn expect(&mut self, token: &Token) -> Result<(), ParseError> {
+    if self.eat(token) {
+      Ok(())
+    } else {
+      Err(self.unexpected(&describe(token)))
+    }
+  }
+
+  /// Consumes the next token if it is `token`.
+  fn eat(&mut self, token: &Token) -> bool {
+    let matches = self.peek() == token;
+    if matches {
+      self.advance();
+    }
+    matches
+  }
+
+  fn unexpected(&self, expected: &str) -> ParseError {
+    ParseError {
+      kind: ParseErrorKind::Unexpected {
+        expected: expected.to_string(),
+        found: describe(self.peek()),
+      },
+      span: self.span(),
+    }
+  }
+
+  fn peek(&self) -> &Token {
+    self.peek_nth(0)
+  }
+
+  /// The `n`th token after the next one, skipping line breaks.
+  fn peek_nth(&self, n: usize) -> &Token {
+    self
+      .upcoming()
+      .nth(n)
+      .map_or(&Token::EndOfFile, |(token, _)| token)
+  }
+
+  /// Where the next token is.
+  fn span(&self) -> Span {
+    self
+      .upcoming()
+      .next()
+      .map(|(_, span)| span.clone())
+      .unwrap_or_default()
+  }
+
+  fn upcoming(&self) -> impl Iterator<Item = &(Token, Span)> {
+    self.tokens[self.pos..]
+      .iter()
+      .filter(|(token, _)| *token != Token::NewLine)
+  }
+
+  fn advance(&mut self) {
+    while self.tokens.get(self.pos).map(|(token, _)| token) == Some(&Token::NewLine) {
+      self.pos += 1;
+    }
+    if self.pos < self.tokens.len() - 1 {
+      self.pos += 1;
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::source::Source;
+  use crate::tokenizer::{CaretPos, Tokenizer};
+
+  fn parse(source: &str) -> Result<Module, ParseError> {
+    let tokens = Tokenizer::default().tokenize_str(source).unwrap();
+    Parser::new(&tokens).parse_module()
+  }
+
+  fn parse_automatic(source: &str) -> Result<Module, ParseError> {
+    let tokens = Tokenizer::default().tokenize_str(source).unwrap();
+    Parser::new(&tokens)
+      .automatic_semicolons(true)
+      .parse_module()
+  }
+
+  fn parse_file(path: &str) -> Module {
+    let tokens = Tokenizer::default().tokenize(&Source::from(path)).unwrap();
+    Parser::new(&tokens).parse_module().unwrap()
+  }
+
+  fn identifier(name: &str) -> Box<Expression> {
+    Box::new(Expression::Identifier(name.to_string()))
+  }
+
+  #[test]
+  fn main_fn() {
+    assert_eq!(
+      parse_file("test/tokenizer/main_fn.fl").items,
+      [Item::Function(Function {
+        name: "main".to_string(),
+        parameters: Vec::new(),
+        return_type: None,
+        body: None,
+      })]
+    );
+  }
+
+  #[test]
+  fn add() {
+    let parameter = |name: &str| Parameter {
+      name: name.to_string(),
+      parameter_type: Type::Primitive("u8".to_string()),
+    };
+
+    assert_eq!(
+      parse_file("test/tokenizer/add.fl").items,
+      [Item::Function(Function {
+        name: "add".to_string(),
+        parameters: alloc::vec![parameter("a"), parameter("b")],
+        return_type: Some(Type::Primitive("u8".to_string())),
+        body: Some(Block {
+          statements: alloc::vec![Statement::Return(Some(Expression::Binary {
+            operator: OperatorType::Addition,
+            left: identifier("a"),
+            right: identifier("b"),
+          }))],
+        }),
+      })]
+    );
+  }
+
+  #[test]
+  fn several_items() {
+    let module = parse_file("test/tokenizer/other_fn.fl");
+
+    assert_eq!(module.items.len(), 2);
+  }
+
+  #[test]
+  fn precedence() {
+    let module = parse("f() {\n  return !a || b + c * d < e;\n};").unwrap();
+    let Item::Function(function) = &module.items[0];
+
+    assert_eq!(
+      function.body.as_ref().unwrap().statements[0],
+      Statement::Return(Some(Expression::Binary {
+        operator: OperatorType::LogicalOr,
+        left: Box::new(Expression::Unary {
+          operator: OperatorType::LogicalNot,
+          operand: identifier("a"),
+        }),
+        right: Box::new(Expression::Binary {
+          operator: OperatorType::LessThan,
+          left: Box::new(Expression::Binary {
+            operator: OperatorType::Addition,
+            left: identifier("b"),
+            right: Box::new(Expression::Binary {
+              operator: OperatorType::Multiplication,
+              left: identifier("c"),
+              right: identifier("d"),
+            }),
+          }),
+          right: identifier("e"),
+        }),
+      }))
+    );
+  }
+
+  #[test]
+  fn calls() {
+    let module = parse("f() {\n  print(\"hi\", g(x),);\n  return;\n};").unwrap();
+    let Item::Function(function) = &module.items[0];
+
+    assert_eq!(
+      function.body.as_ref().unwrap().statements,
+      [
+        Statement::Expression(Expression::Call {
+          callee: identifier("print"),
+          arguments: alloc::vec![
+            Expression::String("hi".to_string()),
+            Expression::Call {
+              callee: identifier("g"),
+              arguments: alloc::vec![Expression::Identifier("x".to_string())],
+            },
+          ],
+        }),
+        Statement::Return(None),
+      ]
+    );
+  }
+
+  #[test]
+  fn integers() {
+    let module = parse("f() {\n  return 0xFF + 1_000u16;\n};").unwrap();
+    let Item::Function(function) = &module.items[0];
+
+    assert_eq!(
+      function.body.as_ref().unwrap().statements[0],
+      Statement::Return(Some(Expression::Binary {
+        operator: OperatorType::Addition,
+        left: Box::new(Expression::Integer {
+          value: 255,
+          suffix: None,
+        }),
+        right: Box::new(Expression::Integer {
+          value: 1000,
+          suffix: Some("u16".to_string()),
+        }),
+      }))
+    );
+  }
+
+  #[test]
+  fn primitive_types_are_keywords() {
+    for primitive in Type::PRIMITIVES {
+      assert!(is_keyword(primitive), "{}", primitive);
+    }
+  }
+
+  #[test]
+  fn unexpected() {
+    let cases = [
+      ("main()", "expected `;`, found end of file"),
+      ("main(a u8);", "expected `:`, found keyword `u8`"),
+      (
+        "main(a: u8 b: u8);",
+        "expected `,` or `)`, found identifier `b`",
+      ),
+      ("add(): -> {};", "expected a type, found `{`"),
+      ("f() { return a +; };", "expected an expression, found `;`"),
+      (
+        "return a;",
+        "expected a function name, found keyword `return`",
+      ),
+    ];
+
+    for (source, message) in cases {
+      assert_eq!(
+        parse(source).unwrap_err().to_string(),
+        message,
+        "{}",
+        source
+      );
+    }
+  }
+
+  #[test]
+  fn error_span() {
+    let err = parse("main(a u8);").unwrap_err();
+
+    assert_eq!(err.span.start, CaretPos::from((1, 8)));
+    assert_eq!(err.span.byte_range, 7..9);
+  }
+
+  #[test]
+  fn misspelled_keyword() {
+    assert_eq!(
+      parse("add(a: u8, b: u8): -> u8 {\n  retrun a + b;\n};")
+        .unwrap_err()
+        .kind,
+      ParseErrorKind::MisspelledKeyword {
+        found: "retrun".to_string(),
+        keyword: "return".to_string(),
+      }
+    );
+  }
+
+  #[test]
+  fn automatic_semicolons() {
+    let source = "greet(name: string) {\n  print(\"hi, \",\n    name)\n  return a\n    + b\n  f()\n  (g)\n  return\n}\nmain()\n";
+    let module = parse_automatic(source).unwrap();
+    let Item::Function(greet) = &module.items[0];
+
+    assert_eq!(module.items.len(), 2);
+    assert_eq!(
+      greet.body.as_ref().unwrap().statements,
+      [
+        Statement::Expression(Expression::Call {
+          callee: identifier("print"),
+          arguments: alloc::vec![
+            Expression::String("hi, ".to_string()),
+            Expression::Identifier("name".to_string()),
+          ],
+        }),
+        Statement::Return(Some(Expression::Binary {
+          operator: OperatorType::Addition,
+          left: identifier("a"),
+          right: identifier("b"),
+        })),
+        Statement::Expression(Expression::Call {
+          callee: identifier("f"),
+          arguments: Vec::new(),
+        }),
+        Statement::Expression(Expression::Identifier("g".to_string())),
+        Statement::Return(None),
+      ]
+    );
+    assert_eq!(
+      parse(source).unwrap_err().to_string(),
+      "expected `;`, found keyword `return`"
+    );
+  }
+
+  #[test]
+  fn automatic_semicolons_keep_explicit_ones() {
+    let tokens = Tokenizer::default()
+      .tokenize(&Source::from("test/tokenizer/add.fl"))
+      .unwrap();
+    let module = Parser::new(&tokens)
+      .automatic_semicolons(true)
+      .parse_module();
+
+    assert_eq!(module, Ok(parse_file("test/tokenizer/add.fl")));
+    assert_eq!(
+      parse_automatic("f() { return a b }")
+        .unwrap_err()
+        .to_string(),
+      "expected `;`, found identifier `b`"
+    );
+  }
+
+  #[test]
+  fn automatic_semicolons_on_the_next_line() {
+    let module = parse_automatic("f() {\n  x\n  ;\n  return\n  ;\n  g()\n}\n;\nmain()\n;\n");
+
+    assert_eq!(module, parse("f() { x; return; g(); }; main();"));
+    assert_eq!(
+      parse_automatic("f() {\n  x\n  ;;\n}\n")
+        .unwrap_err()
+        .to_string(),
+      "expected an expression, found `;`"
+    );
+  }
+}
@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::ast::Module;
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::compile::{Artifact, Diagnostic, Diagnostics, SourceInput};
+use crate::error::FadeError;
+use crate::observer::{Observer, Phase};
+use crate::parser::Parser;
+use crate::token::Token;
+use crate::tokenizer::{Span, Tokenizer};
+
+/// The name errors use for sources given as text rather than as a file.
+pub const TEXT_INPUT_NAME: &str = "<input>";
+
+/// The state shared by everything compiled with the same configuration.
+///
+/// Files registered with `SessionBuilder::file` shadow the file system, so
+/// editors can compile unsaved buffers.
+#[derive(Default)]
+pub struct Session {
+  target: Option<String>,
+  opt_level: u8,
+  files: HashMap<PathBuf, String>,
+  treat_err_as_bug: bool,
+  automatic_semicolons: bool,
+  observers: Vec<Arc<dyn Observer>>,
+}
+
+impl Debug for Session {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    f.debug_struct("Session")
+      .field("target", &self.target)
+      .field("opt_level", &self.opt_level)
+      .field("files", &self.files)
+      .field("treat_err_as_bug", &self.treat_err_as_bug)
+      .field("automatic_semicolons", &self.automatic_semicolons)
+      .field("observers", &self.observers.len())
+      .finish()
+  }
+}
+
+impl Session {
+  pub fn builder() -> SessionBuilder {
+    SessionBuilder::default()
+  }
+
+  /// The target to compile for, `None` meaning the host.
+  pub fn target(&self) -> Option<&str> {
+    self.target.as_deref()
+  }
+
+  pub fn opt_level(&self) -> u8 {
+    self.opt_level
+  }
+
+  pub fn automatic_semicolons(&self) -> bool {
+    self.automatic_semicolons
+  }
+
+  /// Reads `path` from the in-memory files, falling back to the file system.
+  pub fn read_source(&self, path: &Path) -> std::io::Result<String> {
+    match self.files.get(path) {
+      Some(text) => Ok(text.clone()),
+      None => fs::read_to_string(path),
+    }
+  }
+
+  /// Reads and lexes `input` without parsing it, for tools that want the
+  /// tokens of files that might not parse.
+  pub fn tokenize(&self, input: impl Into<SourceInput>) -> Result<Vec<(Token, Span)>, Diagnostics> {
+    let input = input.into();
+    let path = input_path(&input);
+    let _span = tracing::info_span!("tokenize", path = %path.display()).entered();
+
+    match self.report(&path, self.lex(input, &path, None)) {
+      Ok(result) => result,
+      Err(Cancelled) => unreachable!("a compilation without a token can't be cancelled"),
+    }
+  }
+
+  pub fn compile(&self, input: impl Into<SourceInput>) -> Result<Artifact, Diagnostics> {
+    match self.compile_inner(input.into(), None) {
+      Ok(result) => result,
+      Err(Cancelled) => unreachable!("a compilation without a token can't be cancelled"),
+    }
+  }
+
+  /// Like `compile`, but gives up with `Cancelled` once `cancellation` is
+  /// cancelled, e.g. because an editor's buffer changed again.
+  pub fn compile_cancellable(
+    &self,
+    input: impl Into<SourceInput>,
+    cancellation: &CancellationToken,
+  ) -> Result<Result<Artifact, Diagnostics>, Cancelled> {
+    self.compile_inner(input.into(), Some(cancellation))
+  }
+
+  fn compile_inner(
+    &self,
+    input: SourceInput,
+    cancellation: Option<&CancellationToken>,
+  ) -> Result<Result<Artifact, Diagnostics>, Cancelled> {
+    let path = input_path(&input);
+    let _span = tracing::info_span!("compile", path = %path.display()).entered();
+
+    let result = self.report(&path, self.front_end(input, &path, cancellation))?;
+    Ok(result.map(|(tokens, module)| Artifact::new(tokens, module)))
+  }
+
+  /// Turns an error into diagnostics and reports them to the observers,
+  /// unless it's a cancellation.
+  fn report<T>(
+    &self,
+    path: &Path,
+    result: Result<T, FadeError>,
+  ) -> Result<Result<T, Diagnostics>, Cancelled> {
+    match result {
+      Ok(value) => Ok(Ok(value)),
+      Err(FadeError::Cancelled(cancelled)) => {
+        tracing::debug!("cancelled");
+        Err(cancelled)
+      }
+      Err(err) => {
+        let diagnostic = Diagnostic::from(err);
+        tracing::debug!(message = diagnostic.message(), "diagnostic");
+        self.notify(|observer| observer.on_diagnostic(path, &diagnostic));
+        Ok(Err(diagnostic.into()))
+      }
+    }
+  }
+
+  fn front_end(
+    &self,
+    input: SourceInput,
+    path: &Path,
+    cancellation: Option<&CancellationToken>,
+  ) -> Result<(Vec<(Token, Span)>, Module), FadeError> {
+    let tokens = self.lex(input, path, cancellation)?;
+
+    let module = self.phase(
+      Phase::Parse,
+      path,
+      || {
+        Parser::new(&tokens)
+          .automatic_semicolons(self.automatic_semicolons)
+          .parse_module()
+      },
+      |err| FadeError::Parse {
+        path: path.to_path_buf(),
+        source: Box::new(err),
+      },
+    )?;
+    self.notify(|observer| observer.on_file_parsed(path, &module));
+    Ok((tokens, module))
+  }
+
+  fn lex(
+    &self,
+    input: SourceInput,
+    path: &Path,
+    cancellation: Option<&CancellationToken>,
+  ) -> Result<Vec<(Token, Span)>, FadeError> {
+    let check = || cancellation.map_or(Ok(()), CancellationToken::check);
+
+    check()?;
+    let text = match input {
+      SourceInput::Path(_) => self.phase(
+        Phase::Read,
+        path,
+        || self.read_source(path),
+        |err| FadeError::Io {
+          path: path.to_path_buf(),
+          source: err,
+        },
+      )?,
+      SourceInput::Text(text) => text,
+    };
+
+    check()?;
+    let mut tokenizer = match cancellation {
+      Some(cancellation) => Tokenizer::with_cancellation(cancellation.clone()),
+      None => Tokenizer::default(),
+    };
+    let tokens = self.phase(
+      Phase::Lex,
+      path,
+      || tokenizer.tokenize_str(&text),
+      |err| FadeError::Lex {
+        path: path.to_path_buf(),
+        source: err,
+      },
+    )?;
+
+    // The tokenizer stops early when cancelled, so its tokens are incomplete.
+    check()?;
+    self.notify(|observer| observer.on_file_lexed(path, &tokens));
+    Ok(tokens)
+  }
+
+  /// Runs one phase of the front-end. With `treat_err_as_bug` its errors
+  /// panic before the observers hear that it finished, so the panic is
+  /// reported as happening in that phase.
+  fn phase<T, E>(
+    &self,
+    phase: Phase,
+    path: &Path,
+    f: impl FnOnce() -> Result<T, E>,
+    error: impl FnOnce(E) -> FadeError,
+  ) -> Result<T, FadeError> {
+    // Span names have to be static, one per phase keeps flamegraphs readable.
+    let _span = match phase {
+      Phase::Read => tracing::debug_span!("read"),
+      Phase::Lex => tracing::debug_span!("lex"),
+      Phase::Parse => tracing::debug_span!("parse"),
+    }
+    .entered();
+
+    self.notify(|observer| observer.on_phase_start(phase, path));
+    let start = Instant::now();
+    let result = f().map_err(error);
+    let elapsed = start.elapsed();
+    if let Err(err) = &result {
+      if self.treat_err_as_bug {
+        panic!("{}", err);
+      }
+    }
+    self.notify(|observer| observer.on_phase_finish(phase, path, elapsed));
+    result
+  }
+
+  fn notify(&self, f: impl Fn(&dyn Observer)) {
+    self
+      .observers
+      .iter()
+      .for_each(|observer| f(observer.as_ref()));
+  }
+}
+
+fn input_path(input: &SourceInput) -> PathBuf {
+  match input {
+    SourceInput::Path(path) => path.clone(),
+    SourceInput::Text(_) => PathBuf::from(TEXT_INPUT_NAME),
+  }
+}
+
+/// Configures a `Session`.
+///
+/// There is no warning level yet, as the front-end doesn't report any
+/// warnings. Diagnostics aren't rendered by the session either: an
+/// `Observer` receives each one through `on_diagnostic` and decides how to
+/// show it, so that's where a diagnostic emitter plugs in.
+#[derive(Debug, Default)]
+pub struct SessionBuilder {
+  session: Session,
+}
+
+impl SessionBuilder {
+  pub fn target(mut self, target: impl Into<String>) -> Self {
+    self.session.target = Some(target.into());
+    self
+  }
+
+  /// Sets the optimization level, clamped to the supported range of 0 to 3.
+  pub fn opt_level(mut self, opt_level: u8) -> Self {
+    self.session.opt_level = opt_level.min(3);
+    self
+  }
+
+  /// Makes `path` read as `text` instead of its contents on disk.
+  pub fn file(mut self, path: impl Into<PathBuf>, text: impl Into<String>) -> Self {
+    self.session.files.insert(path.into(), text.into());
+    self
+  }
+
+  /// Panics on the first error instead of reporting it, so that errors can
+  /// be debugged like internal compiler errors.
+  pub fn treat_err_as_bug(mut self, treat_err_as_bug: bool) -> Self {
+    self.session.treat_err_as_bug = treat_err_as_bug;
+    self
+  }
+
+  /// Experimental: lets line breaks end statements where a `;` is missing,
+  /// see `Parser::automatic_semicolons`.
+  pub fn automatic_semicolons(mut self, automatic_semicolons: bool) -> Self {
+    self.session.automatic_semicolons = automatic_semicolons;
+    self
+  }
+
+  pub fn observer(mut self, observer: Arc<dyn Observer>) -> Self {
+    self.session.observers.push(observer);
+    self
+  }
+
+  pub fn build(self) -> Session {
+    self.session
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Mutex;
+  use std::time::Duration;
+
+  use super::*;
+
+  #[test]
+  fn builder() {
+    let session = Session::builder()
+      .target("x86_64-linux")
+      .opt_level(7)
+      .build();
+
+    assert_eq!(session.target(), Some("x86_64-linux"));
+    assert_eq!(session.opt_level(), 3);
+  }
+
+  #[test]
+  fn shared_between_threads() {
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    assert_send_sync::<Session>();
+    assert_send_sync::<CancellationToken>();
+  }
+
+  #[test]
+  fn in_memory_file_shadows_disk() {
+    let path = PathBuf::from("test/tokenizer/add.fl");
+    let session = Session::builder().file(&path, "main();\n").build();
+
+    assert_eq!(session.compile(path).unwrap().tokens().len(), 6);
+    assert_eq!(
+      Session::default()
+        .compile(PathBuf::from("test/tokenizer/add.fl"))
+        .unwrap()
+        .tokens()
+        .len(),
+      35
+    );
+  }
+
+  #[test]
+  fn lex_error() {
+    let diagnostics = Session::default()
+      .compile(SourceInput::text(
+        "main();
+x = $y;
+",
+      ))
+      .unwrap_err();
+    let messages: Vec<_> = diagnostics.iter().map(Diagnostic::message).collect();
+
+    assert_eq!(messages, ["<input>:2:5: unexpected character `$`"]);
+  }
+
+  #[test]
+  fn parse_error() {
+    let diagnostics = Session::default()
+      .compile(SourceInput::text("main()\n"))
+      .unwrap_err();
+    let messages: Vec<_> = diagnostics.iter().map(Diagnostic::message).collect();
+
+    assert_eq!(messages, ["<input>:2:1: expected `;`, found end of file"]);
+  }
+
+  #[test]
+  fn tokenize_without_parsing() {
+    let session = Session::default();
+
+    assert_eq!(
+      session.tokenize(SourceInput::text("x = 1;")).unwrap().len(),
+      7
+    );
+    assert!(session.compile(SourceInput::text("x = 1;")).is_err());
+
+    let diagnostics = session.tokenize(SourceInput::text("$")).unwrap_err();
+    let messages: Vec<_> = diagnostics.iter().map(Diagnostic::message).collect();
+    assert_eq!(messages, ["<input>:1:1: unexpected character `$`"]);
+  }
+
+  #[test]
+  fn automatic_semicolons() {
+    let session = Session::builder().automatic_semicolons(true).build();
+
+    assert!(session.compile(SourceInput::text("main()\n")).is_ok());
+  }
+
+  #[derive(Default)]
+  struct Recorder {
+    events: Mutex<Vec<String>>,
+  }
+
+  impl Observer for Recorder {
+    fn on_phase_start(&self, phase: Phase, path: &Path) {
+      self.record(format!("start {} {}", phase, path.display()));
+    }
+
+    fn on_phase_finish(&self, phase: Phase, path: &Path, _elapsed: Duration) {
+      self.record(format!("finish {} {}", phase, path.display()));
+    }
+
+    fn on_file_lexed(&self, path: &Path, tokens: &[(Token, Span)]) {
+      self.record(format!("lexed {} {}", path.display(), tokens.len()));
+    }
+
+    fn on_file_parsed(&self, path: &Path, module: &Module) {
+      self.record(format!("parsed {} {}", path.display(), module.items.len()));
+    }
+
+    fn on_diagnostic(&self, path: &Path, diagnostic: &Diagnostic) {
+      self.record(format!(
+        "diagnostic {} {}",
+        path.display(),
+        diagnostic.message()
+      ));
+    }
+  }
+
+  impl Recorder {
+    fn record(&self, event: String) {
+      self.events.lock().unwrap().push(event);
+    }
+  }
+
+  #[test]
+  fn observer() {
+    let recorder = Arc::new(Recorder::default());
+    let session = Session::builder().observer(recorder.clone()).build();
+
+    session
+      .compile(PathBuf::from("test/tokenizer/main_fn.fl"))
+      .unwrap();
+    session.compile(SourceInput::text("$")).unwrap_err();
+
+    assert_eq!(
+      *recorder.events.lock().unwrap(),
+      [
+        "start read test/tokenizer/main_fn.fl",
+        "finish read test/tokenizer/main_fn.fl",
+        "start lex test/tokenizer/main_fn.fl",
+        "finish lex test/tokenizer/main_fn.fl",
+        "lexed test/tokenizer/main_fn.fl 6",
+        "start parse test/tokenizer/main_fn.fl",
+        "finish parse test/tokenizer/main_fn.fl",
+        "parsed test/tokenizer/main_fn.fl 1",
+        "start lex <input>",
+        "finish lex <input>",
+        "diagnostic <input> <input>:1:1: unexpected character `$`",
+      ]
+    );
+  }
+
+  #[test]
+  fn cancelled() {
+    let cancellation = CancellationToken::new();
+    let session = Session::default();
+
+    assert!(session
+      .compile_cancellable(SourceInput::text("main();\n"), &cancellation)
+      .is_ok());
+
+    cancellation.cancel();
+
+    assert_eq!(
+      session
+        .compile_cancellable(SourceInput::text("main();\n"), &cancellation)
+        .unwrap_err(),
+      Cancelled
+    );
+  }
+
+  #[test]
+  #[should_panic(expected = "<input>:1:1: unexpected character `$`")]
+  fn treat_err_as_bug() {
+    let session = Session::builder().treat_err_as_bug(true).build();
+    let _ = session.compile(SourceInput::text("$"));
+  }
+}
@@ -1,29 +1,41 @@
 use std::fs::File;
 use std::io::BufReader;
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::error::FadeError;
 
 pub struct Source {
   path: PathBuf,
 }
 
 impl Source {
-  //pub fn get_path(&self) -> PathBuf {
-  //  self.path
-  //}
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
 
   pub fn as_file(&self) -> std::io::Result<File> {
     File::open(&self.path)
   }
 
-  pub fn buf_reader(&self) -> BufReader<File> {
-    BufReader::new(self.as_file().unwrap())
+  pub fn buf_reader(&self) -> Result<BufReader<File>, FadeError> {
+    Ok(BufReader::new(self.as_file().map_err(|err| self.io_error(err))?))
   }
 
-  pub fn read_to_string(&self) -> String {
+  pub fn read_to_string(&self) -> Result<String, FadeError> {
     let mut buf = String::default();
-    self.as_file().unwrap().read_to_string(&mut buf).unwrap();
-    buf
+    self
+      .as_file()
+      .and_then(|mut file| file.read_to_string(&mut buf))
+      .map_err(|err| self.io_error(err))?;
+    Ok(buf)
+  }
+
+  fn io_error(&self, err: std::io::Error) -> FadeError {
+    FadeError::Io {
+      path: self.path.clone(),
+      source: err,
+    }
   }
 }
 
@@ -35,16 +47,24 @@ impl From<&str> for Source {
   }
 }
 
+impl From<&Path> for Source {
+  fn from(source: &Path) -> Self {
+    Self {
+      path: source.to_path_buf(),
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
   #[test]
-  fn from_file() -> std::io::Result<()> {
+  fn from_file() -> Result<(), FadeError> {
     let source = Source::from("test/source/tests/from_file.fl");
 
     assert_eq!(
-      source.read_to_string(),
+      source.read_to_string()?,
       "main(): -> u8 := {\n  return 0;\n}\n"
     );
     Ok(())
@@ -1,62 +1,86 @@
-pub trait Token: std::fmt::Debug {}
-
-impl<Rhs: ?Sized + 'static> PartialEq<Rhs> for dyn Token {
-  fn eq(&self, _: &Rhs) -> bool {
-    std::any::TypeId::of::<Self>() == std::any::TypeId::of::<Rhs>()
-  }
-}
-
-impl Eq for dyn Token {}
-
-///////////////////////////////////////////////////////////////////////
-/// EndOfFile, NewLine, Whitespace
-///////////////////////////////////////////////////////////////////////
-
-#[derive(Debug)]
-pub struct EndOfFile;
-
-impl Default for EndOfFile {
-  fn default() -> Self {
-    Self {}
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Formatter};
+
+/// A token of source text. Two tokens are equal if they are of the same kind
+/// and have the same contents.
+///
+/// Part of `fll::api`. New kinds of tokens come with the language, so
+/// matches on it need a wildcard arm.
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum Token {
+  EndOfFile,
+  NewLine,
+  Whitespace,
+  Keyword(Keyword),
+  Identifier(Identifier),
+  StringLiteral(StringLiteral),
+  IntegerLiteral(IntegerLiteral),
+  Parenthesis(Parenthesis),
+  Bracket(Bracket),
+  Brace(Brace),
+  Operator(Operator),
+}
+
+/// Formats like the contents, so `Identifier { identifier: "main" }` rather
+/// than `Identifier(Identifier { identifier: "main" })`.
+impl Debug for Token {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    match self {
+      Token::EndOfFile => write!(f, "EndOfFile"),
+      Token::NewLine => write!(f, "NewLine"),
+      Token::Whitespace => write!(f, "Whitespace"),
+      Token::Keyword(keyword) => keyword.fmt(f),
+      Token::Identifier(identifier) => identifier.fmt(f),
+      Token::StringLiteral(literal) => literal.fmt(f),
+      Token::IntegerLiteral(literal) => literal.fmt(f),
+      Token::Parenthesis(parenthesis) => parenthesis.fmt(f),
+      Token::Bracket(bracket) => bracket.fmt(f),
+      Token::Brace(brace) => brace.fmt(f),
+      Token::Operator(operator) => operator.fmt(f),
+    }
   }
 }
 
-impl Token for EndOfFile {}
-
-#[derive(Debug)]
-pub struct NewLine;
-
-impl Default for NewLine {
-  fn default() -> Self {
-    Self {}
-  }
-}
-
-impl Token for NewLine {}
-
-#[derive(Debug)]
-pub struct Whitespace;
-
-impl Default for Whitespace {
-  fn default() -> Self {
-    Self {}
-  }
+macro_rules! token_from {
+  ($($kind:ident),*) => {
+    $(
+      impl From<$kind> for Token {
+        fn from(token: $kind) -> Self {
+          Token::$kind(token)
+        }
+      }
+    )*
+  };
 }
 
-impl Token for Whitespace {}
+token_from!(
+  Keyword,
+  Identifier,
+  StringLiteral,
+  IntegerLiteral,
+  Parenthesis,
+  Bracket,
+  Brace,
+  Operator
+);
 
 ///////////////////////////////////////////////////////////////////////
 /// Keyword
 ///////////////////////////////////////////////////////////////////////
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Keyword {
   keyword: String,
 }
 
 impl Keyword {
   pub fn is_valid_char(character: &char) -> bool {
-    ('a'..='z').contains(character)
+    character.is_ascii_lowercase()
   }
 }
 
@@ -66,8 +90,6 @@ impl Keyword {
   }
 }
 
-impl Token for Keyword {}
-
 impl From<&str> for Keyword {
   fn from(string: &str) -> Self {
     Self::from(String::from(string))
@@ -80,28 +102,88 @@ impl From<String> for Keyword {
   }
 }
 
+pub const KEYWORDS: &[&str] = &[
+  // Types
+  "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "f32", "f64", "bool", "string",
+  // Literals
+  "true", "false",
+  // Items
+  "impl", "self", "enum", "const", "static", "trait", "for",
+  // Modules
+  "mod", "use", "pub",
+  // Control flow
+  "if", "else", "while", "match", "defer",
+  // Expressions
+  "as", "return",
+];
+
 pub fn is_keyword(string: &str) -> bool {
-  matches!(string, "u8" | "return")
+  KEYWORDS.contains(&string)
+}
+
+/// The keyword `string` is probably a typo of, if exactly one keyword is a
+/// single edit away from it. Swapping two neighbouring characters counts as
+/// one edit, so `retrun` suggests `return`.
+pub fn similar_keyword(string: &str) -> Option<&'static str> {
+  let mut similar = KEYWORDS
+    .iter()
+    .filter(|keyword| edit_distance(string, keyword) == 1);
+
+  match (similar.next(), similar.next()) {
+    (Some(keyword), None) => Some(keyword),
+    _ => None,
+  }
+}
+
+/// The optimal string alignment distance: insertions, deletions,
+/// substitutions and transpositions of neighbouring characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+  let a: Vec<char> = a.chars().collect();
+  let b: Vec<char> = b.chars().collect();
+  // distances[i][j] is the distance between the first i characters of `a`
+  // and the first j of `b`.
+  let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+  for (i, row) in distances.iter_mut().enumerate() {
+    row[0] = i;
+  }
+  for (j, distance) in distances[0].iter_mut().enumerate() {
+    *distance = j;
+  }
+  for i in 1..=a.len() {
+    for j in 1..=b.len() {
+      let cost = usize::from(a[i - 1] != b[j - 1]);
+      let mut distance = (distances[i - 1][j] + 1)
+        .min(distances[i][j - 1] + 1)
+        .min(distances[i - 1][j - 1] + cost);
+      if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+        distance = distance.min(distances[i - 2][j - 2] + 1);
+      }
+      distances[i][j] = distance;
+    }
+  }
+
+  distances[a.len()][b.len()]
 }
 
 ///////////////////////////////////////////////////////////////////////
 /// Identifier
 ///////////////////////////////////////////////////////////////////////
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Identifier {
   identifier: String,
 }
 
 impl Identifier {
   pub fn is_valid_char(character: &char, beginning: bool) -> bool {
-    ('a'..='z').contains(character)
-      || ('A'..='Z').contains(character)
+    character.is_ascii_alphabetic()
       || character == &'_'
       || if beginning {
         false
       } else {
-        ('0'..='9').contains(character)
+        character.is_ascii_digit()
       }
   }
 }
@@ -112,8 +194,6 @@ impl Identifier {
   }
 }
 
-impl Token for Identifier {}
-
 impl From<&str> for Identifier {
   fn from(string: &str) -> Self {
     Self::from(String::from(string))
@@ -126,22 +206,129 @@ impl From<String> for Identifier {
   }
 }
 
+///////////////////////////////////////////////////////////////////////
+/// StringLiteral
+///////////////////////////////////////////////////////////////////////
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StringLiteral {
+  value: String,
+}
+
+impl StringLiteral {
+  pub const QUOTE: char = '"';
+  pub const ESCAPE: char = '\\';
+
+  /// The character an escape sequence like `\n` stands for, given the
+  /// character after the backslash.
+  pub fn unescape(character: char) -> Option<char> {
+    match character {
+      'n' => Some('\n'),
+      'r' => Some('\r'),
+      't' => Some('\t'),
+      '0' => Some('\0'),
+      '\\' => Some('\\'),
+      '"' => Some('"'),
+      _ => None,
+    }
+  }
+}
+
+impl StringLiteral {
+  /// The contents of the literal, with escape sequences resolved.
+  pub fn value(&self) -> String {
+    self.value.clone()
+  }
+}
+
+impl From<&str> for StringLiteral {
+  fn from(string: &str) -> Self {
+    Self::from(String::from(string))
+  }
+}
+
+impl From<String> for StringLiteral {
+  fn from(string: String) -> Self {
+    Self { value: string }
+  }
+}
+
+///////////////////////////////////////////////////////////////////////
+/// IntegerLiteral
+///////////////////////////////////////////////////////////////////////
+
+// `42`, `0xFF`, `0b1010` or `0o755`, with `_` allowed between the digits
+// and an optional type suffix, as in `255_u8`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IntegerLiteral {
+  value: u64,
+  suffix: Option<String>,
+}
+
+impl IntegerLiteral {
+  pub const SEPARATOR: char = '_';
+  /// The integer types a literal can be suffixed with.
+  pub const SUFFIXES: &'static [&'static str] =
+    &["u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64"];
+
+  /// The base given by the character after a leading `0`, like `x` in `0xFF`.
+  pub fn radix(prefix: char) -> Option<u32> {
+    match prefix {
+      'x' => Some(16),
+      'o' => Some(8),
+      'b' => Some(2),
+      _ => None,
+    }
+  }
+
+  pub fn new(value: u64, suffix: Option<&str>) -> Self {
+    Self {
+      value,
+      suffix: suffix.map(String::from),
+    }
+  }
+}
+
+impl IntegerLiteral {
+  pub fn value(&self) -> u64 {
+    self.value
+  }
+
+  /// The type suffix, e.g. `u8`.
+  pub fn suffix(&self) -> Option<String> {
+    self.suffix.clone()
+  }
+}
+
+impl From<u64> for IntegerLiteral {
+  fn from(value: u64) -> Self {
+    Self::new(value, None)
+  }
+}
+
 ///////////////////////////////////////////////////////////////////////
 /// Paranthesis, Bracket, Brace
 ///////////////////////////////////////////////////////////////////////
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BracketType {
   Opening,
   Closing,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Parenthesis {
   bracket_type: BracketType,
 }
 
-impl Token for Parenthesis {}
+impl Parenthesis {
+  pub const OPEN: char = '(';
+  pub const CLOSE: char = ')';
+}
 
 impl Parenthesis {
   pub fn bracket_type(&self) -> BracketType {
@@ -165,12 +352,16 @@ impl From<BracketType> for Parenthesis {
   }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bracket {
   bracket_type: BracketType,
 }
 
-impl Token for Bracket {}
+impl Bracket {
+  pub const OPEN: char = '[';
+  pub const CLOSE: char = ']';
+}
 
 impl Bracket {
   pub fn bracket_type(&self) -> BracketType {
@@ -194,12 +385,16 @@ impl From<BracketType> for Bracket {
   }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Brace {
   bracket_type: BracketType,
 }
 
-impl Token for Brace {}
+impl Brace {
+  pub const OPEN: char = '{';
+  pub const CLOSE: char = '}';
+}
 
 impl Brace {
   pub fn bracket_type(&self) -> BracketType {
@@ -227,11 +422,13 @@ impl From<BracketType> for Brace {
 /// Operator
 ///////////////////////////////////////////////////////////////////////
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OperatorType {
   // Scoping, Accessing
   ScopeAccessor,  // ::
   MemberAccessor, // ->
+  FieldAccessor,  // .
 
   // Generics
   GenericBlockBegin, // <
@@ -243,6 +440,17 @@ pub enum OperatorType {
   CommaSeparator,      // ,
   StatementTerminator, // ;
 
+  // Patterns
+  MatchArm,       // =>
+  Range,          // ..
+  RangeInclusive, // ..=
+
+  // Errors
+  ErrorPropagation, // ?
+
+  // Attributes
+  Attribute, // #
+
   // Arithmetic
   Addition,       // +
   Subtraction,    // -
@@ -288,13 +496,129 @@ pub enum OperatorType {
   BitwiseOrAssignment,         // |=
 }
 
-#[derive(Debug)]
+impl OperatorType {
+  pub const ALL: [OperatorType; 47] = [
+    OperatorType::ScopeAccessor,
+    OperatorType::MemberAccessor,
+    OperatorType::FieldAccessor,
+    OperatorType::GenericBlockBegin,
+    OperatorType::GenericBlockEnd,
+    OperatorType::TypeSpecifier,
+    OperatorType::ReturnType,
+    OperatorType::CommaSeparator,
+    OperatorType::StatementTerminator,
+    OperatorType::MatchArm,
+    OperatorType::Range,
+    OperatorType::RangeInclusive,
+    OperatorType::ErrorPropagation,
+    OperatorType::Attribute,
+    OperatorType::Addition,
+    OperatorType::Subtraction,
+    OperatorType::Multiplication,
+    OperatorType::Division,
+    OperatorType::Modulo,
+    OperatorType::Equals,
+    OperatorType::NotEquals,
+    OperatorType::LessThan,
+    OperatorType::LessThanOrEqual,
+    OperatorType::GreaterThan,
+    OperatorType::GreaterThanOrEqual,
+    OperatorType::LogicalAnd,
+    OperatorType::LogicalOr,
+    OperatorType::LogicalNot,
+    OperatorType::BitwiseAnd,
+    OperatorType::BitwiseXOr,
+    OperatorType::BitwiseOr,
+    OperatorType::BitwiseNot,
+    OperatorType::BitwiseRightShift,
+    OperatorType::BitwiseLeftShift,
+    OperatorType::ValueAssignment,
+    OperatorType::AdditionAssignment,
+    OperatorType::SubtractionAssignment,
+    OperatorType::MultiplicationAssignment,
+    OperatorType::DivisionAssignment,
+    OperatorType::ModuloAssignment,
+    OperatorType::Increment,
+    OperatorType::Decrement,
+    OperatorType::BitwiseRightShiftAssignment,
+    OperatorType::BitwiseLeftShiftAssignment,
+    OperatorType::BitwiseAndAssignment,
+    OperatorType::BitwiseXOrAssignment,
+    OperatorType::BitwiseOrAssignment,
+  ];
+
+  /// The source text of the operator. Some operators share their text, e.g.
+  /// `<` is both `GenericBlockBegin` and `LessThan`.
+  pub fn symbol(&self) -> &'static str {
+    match self {
+      OperatorType::ScopeAccessor => "::",
+      OperatorType::MemberAccessor => "->",
+      OperatorType::FieldAccessor => ".",
+      OperatorType::GenericBlockBegin => "<",
+      OperatorType::GenericBlockEnd => ">",
+      OperatorType::TypeSpecifier => ":",
+      OperatorType::ReturnType => "->",
+      OperatorType::CommaSeparator => ",",
+      OperatorType::StatementTerminator => ";",
+      OperatorType::MatchArm => "=>",
+      OperatorType::Range => "..",
+      OperatorType::RangeInclusive => "..=",
+      OperatorType::ErrorPropagation => "?",
+      OperatorType::Attribute => "#",
+      OperatorType::Addition => "+",
+      OperatorType::Subtraction => "-",
+      OperatorType::Multiplication => "*",
+      OperatorType::Division => "/",
+      OperatorType::Modulo => "%",
+      OperatorType::Equals => "==",
+      OperatorType::NotEquals => "!=",
+      OperatorType::LessThan => "<",
+      OperatorType::LessThanOrEqual => "<=",
+      OperatorType::GreaterThan => ">",
+      OperatorType::GreaterThanOrEqual => ">=",
+      OperatorType::LogicalAnd => "&&",
+      OperatorType::LogicalOr => "||",
+      OperatorType::LogicalNot => "!",
+      OperatorType::BitwiseAnd => "&",
+      OperatorType::BitwiseXOr => "^",
+      OperatorType::BitwiseOr => "|",
+      OperatorType::BitwiseNot => "~",
+      OperatorType::BitwiseRightShift => ">>",
+      OperatorType::BitwiseLeftShift => "<<",
+      OperatorType::ValueAssignment => "=",
+      OperatorType::AdditionAssignment => "+=",
+      OperatorType::SubtractionAssignment => "-=",
+      OperatorType::MultiplicationAssignment => "*=",
+      OperatorType::DivisionAssignment => "/=",
+      OperatorType::ModuloAssignment => "%=",
+      OperatorType::Increment => "++",
+      OperatorType::Decrement => "--",
+      OperatorType::BitwiseRightShiftAssignment => ">>=",
+      OperatorType::BitwiseLeftShiftAssignment => "<<=",
+      OperatorType::BitwiseAndAssignment => "&=",
+      OperatorType::BitwiseXOrAssignment => "^=",
+      OperatorType::BitwiseOrAssignment => "|=",
+    }
+  }
+
+  /// Whether the tokenizer produces this operator. Of the operators sharing a
+  /// symbol only one is produced, and the parser tells them apart: `<` and
+  /// `>` lex as generic brackets, `->` as a return type. `&`, `*` and `|` also
+  /// stand for references, dereferences and closure parameters.
+  pub fn is_lexed(&self) -> bool {
+    !matches!(
+      self,
+      OperatorType::MemberAccessor | OperatorType::LessThan | OperatorType::GreaterThan
+    )
+  }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Operator {
   operator_type: OperatorType,
 }
 
-impl Token for Operator {}
-
 impl Operator {
   pub fn operator_type(&self) -> OperatorType {
     self.operator_type
@@ -306,3 +630,30 @@ impl From<OperatorType> for Operator {
     Self { operator_type }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn edit_distances() {
+    assert_eq!(edit_distance("return", "return"), 0);
+    assert_eq!(edit_distance("retrun", "return"), 1);
+    assert_eq!(edit_distance("retur", "return"), 1);
+    assert_eq!(edit_distance("whilee", "while"), 1);
+    assert_eq!(edit_distance("mach", "match"), 1);
+    assert_eq!(edit_distance("", "as"), 2);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+  }
+
+  #[test]
+  fn similar_keywords() {
+    assert_eq!(similar_keyword("retrun"), Some("return"));
+    assert_eq!(similar_keyword("whlie"), Some("while"));
+    assert_eq!(similar_keyword("elss"), Some("else"));
+    // `u8` and `i8` are both one edit away.
+    assert_eq!(similar_keyword("x8"), None);
+    assert_eq!(similar_keyword("return"), None);
+    assert_eq!(similar_keyword("value"), None);
+  }
+}
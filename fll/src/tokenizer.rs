@@ -1,101 +1,288 @@
-use std::fmt::{Display, Formatter};
-
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+use core::iter::Peekable;
+use core::ops::Range;
+use core::str::Chars;
+
+#[cfg(feature = "std")]
+use crate::error::FadeError;
+use crate::cancel::CancellationToken;
+use crate::error::{LexError, LexErrorKind};
+#[cfg(feature = "std")]
 use crate::source::Source;
 use crate::token::*;
 
+#[derive(Default)]
 pub struct Tokenizer {
   caret_pos: CaretPos,
+  /// The byte offset of `caret_pos`.
+  offset: usize,
+  cancellation: Option<CancellationToken>,
 }
 
 impl Tokenizer {
-  pub fn tokenize(&mut self, source: &Source) -> std::io::Result<Vec<Box<dyn Token>>> {
-    let source = source.read_to_string();
-    let mut tokens = Vec::<Box<dyn Token>>::new();
+  /// A tokenizer that stops at the next line break once `cancellation` is
+  /// cancelled, returning the tokens up to there.
+  pub fn with_cancellation(cancellation: CancellationToken) -> Self {
+    Self {
+      cancellation: Some(cancellation),
+      ..Self::default()
+    }
+  }
 
-    let mut chars = source.chars().peekable();
+  #[cfg(feature = "std")]
+  pub fn tokenize(&mut self, source: &Source) -> Result<Vec<(Token, Span)>, FadeError> {
+    self
+      .tokenize_str(&source.read_to_string()?)
+      .map_err(|err| FadeError::Lex {
+        path: source.path().to_path_buf(),
+        source: err,
+      })
+  }
 
-    loop {
-      let char_cur = chars.next();
+  /// The tokens of `source`, each with where it is in `source`. The last one
+  /// is always `EndOfFile`, unless the tokenizer was cancelled.
+  pub fn tokenize_str(&mut self, source: &str) -> Result<Vec<(Token, Span)>, LexError> {
+    let mut tokens = Vec::new();
 
-      self.caret_pos.process_char(char_cur);
+    let mut chars = source.chars().peekable();
 
-      if char_cur == None {
-        tokens.push(Box::new(EndOfFile::default()));
+    loop {
+      let pos = self.caret_pos;
+      let offset = self.offset;
+      let Some(char_cur) = self.next_char(&mut chars) else {
+        tokens.push((Token::EndOfFile, self.span(pos, offset)));
         break;
-      } else if let Some(char_cur) = char_cur {
-        if char_cur == '(' {
-          tokens.push(Box::new(Parenthesis::open()));
-        } else if char_cur == ')' {
-          tokens.push(Box::new(Parenthesis::close()));
-        } else if char_cur == '<' {
-          tokens.push(Box::new(Operator::from(OperatorType::GenericBlockBegin)));
-        } else if char_cur == '>' {
-          tokens.push(Box::new(Operator::from(OperatorType::GenericBlockEnd)));
-        } else if char_cur == ';' {
-          tokens.push(Box::new(Operator::from(OperatorType::StatementTerminator)));
-        } else if char_cur == ':' {
-          tokens.push(Box::new(Operator::from(OperatorType::TypeSpecifier)));
-        } else if char_cur == ',' {
-          tokens.push(Box::new(Operator::from(OperatorType::CommaSeparator)));
-        } else if char_cur == '-' && chars.peek().unwrap() == &'>' {
-          chars.next().unwrap();
-          tokens.push(Box::new(Operator::from(OperatorType::ReturnType)));
-        } else if char_cur == '+' {
-          tokens.push(Box::new(Operator::from(OperatorType::Addition)));
-        } else if char_cur == '{' {
-          tokens.push(Box::new(Brace::open()));
-        } else if char_cur == '}' {
-          tokens.push(Box::new(Brace::close()));
-        } else if char_cur == ' ' {
-          while let Some(' ') = chars.peek() {
-            chars.next();
-          }
-          tokens.push(Box::new(Whitespace::default()));
-        } else if char_cur == '\n' {
-          tokens.push(Box::new(NewLine::default()));
-        } else if Identifier::is_valid_char(&char_cur, true) || Keyword::is_valid_char(&char_cur) {
-          let mut buf = String::from(char_cur);
-
-          while let Some(peek) = chars.peek() {
-            if Identifier::is_valid_char(peek, buf.is_empty())
-              || Keyword::is_valid_char(peek)
-            {
-              buf.push(chars.next().unwrap());
-            } else {
-              break;
-            }
+      };
+
+      let token = if char_cur == '(' {
+        Parenthesis::open().into()
+      } else if char_cur == ')' {
+        Parenthesis::close().into()
+      } else if let Some(operator_type) = self.operator(char_cur, &mut chars) {
+        Operator::from(operator_type).into()
+      } else if char_cur == Bracket::OPEN {
+        Bracket::open().into()
+      } else if char_cur == Bracket::CLOSE {
+        Bracket::close().into()
+      } else if char_cur == '{' {
+        Brace::open().into()
+      } else if char_cur == '}' {
+        Brace::close().into()
+      } else if char_cur == StringLiteral::QUOTE {
+        self.string_literal(&mut chars, pos)?.into()
+      } else if char_cur.is_ascii_digit() {
+        self.integer_literal(char_cur, &mut chars, pos)?.into()
+      } else if char_cur == ' ' {
+        while let Some(' ') = chars.peek() {
+          self.next_char(&mut chars);
+        }
+        Token::Whitespace
+      } else if char_cur == '\n' {
+        Token::NewLine
+      } else if Identifier::is_valid_char(&char_cur, true) || Keyword::is_valid_char(&char_cur) {
+        let mut buf = String::from(char_cur);
+
+        while let Some(&peek) = chars.peek() {
+          if Identifier::is_valid_char(&peek, buf.is_empty()) || Keyword::is_valid_char(&peek) {
+            self.next_char(&mut chars);
+            buf.push(peek);
+          } else {
+            break;
           }
+        }
 
-          if !buf.is_empty() {
-            if is_keyword(&buf) {
-              tokens.push(Box::new(Keyword::from(buf.clone())));
-            } else {
-              tokens.push(Box::new(Identifier::from(buf.clone())));
-            }
-          }
+        if is_keyword(&buf) {
+          Keyword::from(buf).into()
         } else {
-          panic!("Character '{}' was not handled", char_cur);
+          Identifier::from(buf).into()
         }
+      } else {
+        return Err(LexError {
+          kind: LexErrorKind::UnexpectedChar(char_cur),
+          pos,
+          width: 1,
+        });
+      };
+
+      let is_new_line = token == Token::NewLine;
+      tokens.push((token, self.span(pos, offset)));
+
+      if is_new_line && self.cancellation.as_ref().is_some_and(CancellationToken::is_cancelled) {
+        break;
       }
     }
 
     Ok(tokens)
   }
 
-  pub fn get_caret_pos(&self) -> CaretPos {
-    self.caret_pos
+  /// The span from `start`, at byte `start_offset`, up to the next character.
+  fn span(&self, start: CaretPos, start_offset: usize) -> Span {
+    Span {
+      start,
+      end: self.caret_pos,
+      byte_range: start_offset..self.offset,
+    }
   }
-}
 
-impl Default for Tokenizer {
-  fn default() -> Self {
-    Self {
-      caret_pos: CaretPos::default(),
+  /// Lexes the rest of a string literal, after its opening quote at `start`.
+  fn string_literal(
+    &mut self,
+    chars: &mut Peekable<Chars<'_>>,
+    start: CaretPos,
+  ) -> Result<StringLiteral, LexError> {
+    let mut value = String::new();
+
+    loop {
+      let pos = self.caret_pos;
+
+      match self.next_char(chars) {
+        None => {
+          return Err(LexError {
+            kind: LexErrorKind::UnterminatedString,
+            pos: start,
+            width: 1,
+          })
+        }
+        Some(StringLiteral::QUOTE) => return Ok(StringLiteral::from(value)),
+        Some(StringLiteral::ESCAPE) => match self.next_char(chars) {
+          Some(escape) => match StringLiteral::unescape(escape) {
+            Some(character) => value.push(character),
+            None => {
+              return Err(LexError {
+                kind: LexErrorKind::UnknownEscape(escape),
+                pos,
+                width: 2,
+              })
+            }
+          },
+          None => {
+            return Err(LexError {
+              kind: LexErrorKind::UnterminatedString,
+              pos: start,
+              width: 1,
+            })
+          }
+        },
+        Some(character) => value.push(character),
+      }
+    }
+  }
+
+  /// Lexes the rest of an integer literal starting with the digit `first` at
+  /// `start`.
+  fn integer_literal(
+    &mut self,
+    first: char,
+    chars: &mut Peekable<Chars<'_>>,
+    start: CaretPos,
+  ) -> Result<IntegerLiteral, LexError> {
+    let mut radix = 10;
+    let mut digits = String::new();
+
+    match chars.peek().copied().and_then(IntegerLiteral::radix) {
+      Some(prefix_radix) if first == '0' => {
+        self.next_char(chars);
+        radix = prefix_radix;
+      }
+      _ => digits.push(first),
+    }
+
+    while let Some(&peek) = chars.peek() {
+      if peek.is_digit(radix) {
+        digits.push(peek);
+      } else if peek.is_ascii_digit() {
+        return Err(LexError {
+          kind: LexErrorKind::InvalidDigit { digit: peek, radix },
+          pos: self.caret_pos,
+          width: 1,
+        });
+      } else if peek != IntegerLiteral::SEPARATOR {
+        break;
+      }
+      self.next_char(chars);
+    }
+    if digits.is_empty() {
+      return Err(LexError {
+        kind: LexErrorKind::MissingDigits,
+        pos: start,
+        width: 2,
+      });
+    }
+
+    let suffix_pos = self.caret_pos;
+    let mut suffix = String::new();
+    while let Some(&peek) = chars.peek() {
+      if !Identifier::is_valid_char(&peek, false) {
+        break;
+      }
+      self.next_char(chars);
+      suffix.push(peek);
     }
+    if !suffix.is_empty() && !IntegerLiteral::SUFFIXES.contains(&suffix.as_str()) {
+      return Err(LexError {
+        kind: LexErrorKind::InvalidSuffix,
+        pos: suffix_pos,
+        width: suffix.chars().count(),
+      });
+    }
+
+    // A literal is on one line, so its width is the distance to its suffix.
+    let value = u64::from_str_radix(&digits, radix).map_err(|_| LexError {
+      kind: LexErrorKind::IntegerTooLarge,
+      pos: start,
+      width: suffix_pos.get_column() - start.get_column(),
+    })?;
+    Ok(IntegerLiteral::new(
+      value,
+      Some(suffix.as_str()).filter(|suffix| !suffix.is_empty()),
+    ))
+  }
+
+  /// Lexes the longest operator symbol starting with `first`, if there is
+  /// one, and consumes the rest of it.
+  fn operator(&mut self, first: char, chars: &mut Peekable<Chars<'_>>) -> Option<OperatorType> {
+    let mut longest: Option<OperatorType> = None;
+
+    for operator_type in OperatorType::ALL.iter().filter(|o| o.is_lexed()) {
+      let mut symbol = operator_type.symbol().chars();
+      if symbol.next() != Some(first) {
+        continue;
+      }
+
+      let mut ahead = chars.clone();
+      let matches = symbol.all(|expected| ahead.next() == Some(expected));
+      let longer = longest.is_none_or(|longest| {
+        operator_type.symbol().len() > longest.symbol().len()
+      });
+      if matches && longer {
+        longest = Some(*operator_type);
+      }
+    }
+
+    let operator_type = longest?;
+    for _ in 1..operator_type.symbol().chars().count() {
+      self.next_char(chars);
+    }
+    Some(operator_type)
+  }
+
+  /// Consumes the next character, keeping the caret position up to date.
+  fn next_char(&mut self, chars: &mut Peekable<Chars<'_>>) -> Option<char> {
+    let char_cur = chars.next();
+    self.caret_pos.process_char(char_cur);
+    self.offset += char_cur.map_or(0, char::len_utf8);
+    char_cur
+  }
+
+  pub fn get_caret_pos(&self) -> CaretPos {
+    self.caret_pos
   }
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CaretPos {
   line: usize,
   column: usize,
@@ -156,119 +343,119 @@ impl From<(usize, usize)> for CaretPos {
 }
 
 impl Display for CaretPos {
-  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
     write!(f, ":{}:{}", self.get_line(), self.get_column())
   }
 }
 
+/// Where a token is in the source text. `end` and the end of `byte_range`
+/// are right after its last character, so `EndOfFile` covers nothing.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Span {
+  pub start: CaretPos,
+  pub end: CaretPos,
+  pub byte_range: Range<usize>,
+}
+
 #[cfg(test)]
 mod tests {
+  use crate::cancel::CancellationToken;
+  use crate::error::{LexError, LexErrorKind};
   use crate::source::Source;
   use crate::token::*;
-  use crate::tokenizer::{Tokenizer, CaretPos};
+  use crate::tokenizer::{Tokenizer, CaretPos, Span};
 
-  macro_rules! tokens_equal {
-    ($expected:expr, $actual:expr) => {
-      println!("(expected) > {:?}", $expected);
-      println!(" (actual)  > {:?}", $actual);
-
-      assert_eq!($expected.len(), $actual.len());
-    };
+  fn without_spans(tokens: Vec<(Token, Span)>) -> Vec<Token> {
+    tokens.into_iter().map(|(token, _)| token).collect()
   }
 
   #[test]
   fn tokenize_main_fn() {
     let source = Source::from("test/tokenizer/main_fn.fl");
     let mut tokenizer = Tokenizer::default();
-    let tokenized = tokenizer.tokenize(&source).unwrap();
-
-    let tokenized_eq: Vec<Box<dyn Token>> = vec![
-      Box::new(Identifier::from("main")),
-      Box::new(Parenthesis::open()),
-      Box::new(Parenthesis::close()),
-      Box::new(Operator::from(OperatorType::StatementTerminator)),
-      Box::new(NewLine::default()),
-      Box::new(EndOfFile::default()),
+    let tokenized = without_spans(tokenizer.tokenize(&source).unwrap());
+
+    let tokenized_eq: Vec<Token> = vec![
+      Identifier::from("main").into(),
+      Parenthesis::open().into(),
+      Parenthesis::close().into(),
+      Operator::from(OperatorType::StatementTerminator).into(),
+      Token::NewLine,
+      Token::EndOfFile,
     ];
 
-    println!("tc > {:?}", tokenized);
-    println!("eq > {:?}", tokenized_eq);
-
-    assert_eq!(tokenized.len(), tokenized_eq.len());
-
-    // for i in 0..tokenized.len() {
-    //   assert_eq!(tokenized[i], tokenized_eq[i])
-    // }
+    assert_eq!(tokenized, tokenized_eq);
   }
 
   #[test]
   fn tokenize_other_fn() {
     let source = Source::from("test/tokenizer/other_fn.fl");
     let mut tokenizer = Tokenizer::default();
-    let actual = tokenizer.tokenize(&source).unwrap();
-
-    let expected: Vec<Box<dyn Token>> = vec![
-      Box::new(Identifier::from("man")),
-      Box::new(Parenthesis::open()),
-      Box::new(Parenthesis::close()),
-      Box::new(Operator::from(OperatorType::StatementTerminator)),
-      Box::new(NewLine::default()),
-      Box::new(Identifier::from("other_fn")),
-      Box::new(Parenthesis::open()),
-      Box::new(Parenthesis::close()),
-      Box::new(Operator::from(OperatorType::StatementTerminator)),
-      Box::new(NewLine::default()),
-      Box::new(EndOfFile::default()),
+    let actual = without_spans(tokenizer.tokenize(&source).unwrap());
+
+    let expected: Vec<Token> = vec![
+      Identifier::from("main").into(),
+      Parenthesis::open().into(),
+      Parenthesis::close().into(),
+      Operator::from(OperatorType::StatementTerminator).into(),
+      Token::NewLine,
+      Identifier::from("other_fn").into(),
+      Parenthesis::open().into(),
+      Parenthesis::close().into(),
+      Operator::from(OperatorType::StatementTerminator).into(),
+      Token::NewLine,
+      Token::EndOfFile,
     ];
 
-    tokens_equal!(expected, actual);
+    assert_eq!(actual, expected);
   }
 
   #[test]
   fn tokenize_add() {
     let source = Source::from("test/tokenizer/add.fl");
     let mut tokenizer = Tokenizer::default();
-    let actual = tokenizer.tokenize(&source).unwrap();
-
-    let expected: Vec<Box<dyn Token>> = vec![
-      Box::new(Identifier::from("add")),
-      Box::new(Parenthesis::open()),
-      Box::new(Identifier::from("a")),
-      Box::new(Operator::from(OperatorType::TypeSpecifier)),
-      Box::new(Whitespace::default()),
-      Box::new(Keyword::from("u8")),
-      Box::new(Operator::from(OperatorType::CommaSeparator)),
-      Box::new(Whitespace::default()),
-      Box::new(Identifier::from("b")),
-      Box::new(Operator::from(OperatorType::TypeSpecifier)),
-      Box::new(Whitespace::default()),
-      Box::new(Keyword::from("u8")),
-      Box::new(Parenthesis::close()),
-      Box::new(Operator::from(OperatorType::TypeSpecifier)),
-      Box::new(Whitespace::default()),
-      Box::new(Operator::from(OperatorType::ReturnType)),
-      Box::new(Whitespace::default()),
-      Box::new(Keyword::from("u8")),
-      Box::new(Whitespace::default()),
-      Box::new(Brace::open()),
-      Box::new(NewLine::default()),
-      Box::new(Whitespace::default()),
-      Box::new(Keyword::from("return")),
-      Box::new(Whitespace::default()),
-      Box::new(Identifier::from("a")),
-      Box::new(Whitespace::default()),
-      Box::new(Operator::from(OperatorType::Addition)),
-      Box::new(Whitespace::default()),
-      Box::new(Identifier::from("b")),
-      Box::new(Operator::from(OperatorType::StatementTerminator)),
-      Box::new(NewLine::default()),
-      Box::new(Brace::close()),
-      Box::new(Operator::from(OperatorType::StatementTerminator)),
-      Box::new(NewLine::default()),
-      Box::new(EndOfFile::default()),
+    let actual = without_spans(tokenizer.tokenize(&source).unwrap());
+
+    let expected: Vec<Token> = vec![
+      Identifier::from("add").into(),
+      Parenthesis::open().into(),
+      Identifier::from("a").into(),
+      Operator::from(OperatorType::TypeSpecifier).into(),
+      Token::Whitespace,
+      Keyword::from("u8").into(),
+      Operator::from(OperatorType::CommaSeparator).into(),
+      Token::Whitespace,
+      Identifier::from("b").into(),
+      Operator::from(OperatorType::TypeSpecifier).into(),
+      Token::Whitespace,
+      Keyword::from("u8").into(),
+      Parenthesis::close().into(),
+      Operator::from(OperatorType::TypeSpecifier).into(),
+      Token::Whitespace,
+      Operator::from(OperatorType::ReturnType).into(),
+      Token::Whitespace,
+      Keyword::from("u8").into(),
+      Token::Whitespace,
+      Brace::open().into(),
+      Token::NewLine,
+      Token::Whitespace,
+      Keyword::from("return").into(),
+      Token::Whitespace,
+      Identifier::from("a").into(),
+      Token::Whitespace,
+      Operator::from(OperatorType::Addition).into(),
+      Token::Whitespace,
+      Identifier::from("b").into(),
+      Operator::from(OperatorType::StatementTerminator).into(),
+      Token::NewLine,
+      Brace::close().into(),
+      Operator::from(OperatorType::StatementTerminator).into(),
+      Token::NewLine,
+      Token::EndOfFile,
     ];
 
-    tokens_equal!(expected, actual);
+    assert_eq!(actual, expected);
   }
 
   #[test]
@@ -281,4 +468,416 @@ mod tests {
 
     assert_eq!(actual, expected)
   }
+
+  #[test]
+  fn spans() {
+    let actual = Tokenizer::default().tokenize_str("x = \"é\"\n").unwrap();
+    let span = |start: (usize, usize), end: (usize, usize), byte_range| Span {
+      start: CaretPos::from(start),
+      end: CaretPos::from(end),
+      byte_range,
+    };
+
+    assert_eq!(
+      actual.into_iter().map(|(_, span)| span).collect::<Vec<_>>(),
+      [
+        span((1, 1), (1, 2), 0..1),
+        span((1, 2), (1, 3), 1..2),
+        span((1, 3), (1, 4), 2..3),
+        span((1, 4), (1, 5), 3..4),
+        span((1, 5), (1, 8), 4..8),
+        span((1, 8), (2, 1), 8..9),
+        span((2, 1), (2, 1), 9..9),
+      ]
+    );
+  }
+
+  fn tokens(source: &str) -> Vec<Token> {
+    without_spans(Tokenizer::default().tokenize_str(source).unwrap())
+  }
+
+  fn keyword(keyword: &str) -> Token {
+    Keyword::from(keyword).into()
+  }
+
+  fn identifier(identifier: &str) -> Token {
+    Identifier::from(identifier).into()
+  }
+
+  fn operator(operator_type: OperatorType) -> Token {
+    Operator::from(operator_type).into()
+  }
+
+  #[test]
+  fn number_types() {
+    let actual = tokens("u16 u32 u64 i8 i16 i32 i64 as f32 f64");
+
+    assert_eq!(
+      actual
+        .iter()
+        .filter(|token| matches!(token, Token::Keyword(_)))
+        .count(),
+      10
+    );
+    assert_eq!(actual[6], keyword("i8"));
+  }
+
+  #[test]
+  fn logical_operators() {
+    let actual = tokens("!a&&b||c!=true");
+
+    assert_eq!(
+      actual,
+      [
+        operator(OperatorType::LogicalNot),
+        identifier("a"),
+        operator(OperatorType::LogicalAnd),
+        identifier("b"),
+        operator(OperatorType::LogicalOr),
+        identifier("c"),
+        operator(OperatorType::NotEquals),
+        keyword("true"),
+        Token::EndOfFile,
+      ]
+    );
+  }
+
+  #[test]
+  fn string_literal() {
+    let actual = tokens(r#"greeting: string "say \"hi\"\n""#);
+
+    assert_eq!(actual[3], keyword("string"));
+    assert_eq!(actual[5], StringLiteral::from("say \"hi\"\n").into());
+  }
+
+  #[test]
+  fn invalid_string_literals() {
+    let mut tokenizer = Tokenizer::default();
+
+    assert_eq!(
+      tokenizer.tokenize_str("main(\"abc").unwrap_err(),
+      LexError {
+        kind: LexErrorKind::UnterminatedString,
+        pos: CaretPos::from((1, 6)),
+        width: 1,
+      }
+    );
+
+    let mut tokenizer = Tokenizer::default();
+
+    assert_eq!(
+      tokenizer.tokenize_str("\"a\\qb\"").unwrap_err(),
+      LexError {
+        kind: LexErrorKind::UnknownEscape('q'),
+        pos: CaretPos::from((1, 3)),
+        width: 2,
+      }
+    );
+  }
+
+  #[test]
+  fn unknown_escape_span() {
+    let mut tokenizer = Tokenizer::default();
+    let actual = tokenizer
+      .tokenize_str("s = \"first\n  \\t\\é\";\n")
+      .unwrap_err();
+
+    assert_eq!(actual.kind, LexErrorKind::UnknownEscape('é'));
+    assert_eq!(actual.pos, CaretPos::from((2, 5)));
+    assert_eq!(actual.end(), CaretPos::from((2, 7)));
+  }
+
+  #[test]
+  fn integer_literals() {
+    let mut tokenizer = Tokenizer::default();
+    let actual = tokenizer
+      .tokenize_str("42 0xFF 0b1010 0o755 1_000 0u8 255_u8")
+      .unwrap();
+
+    assert_eq!(
+      without_spans(actual)
+        .into_iter()
+        .filter(|token| *token != Token::Whitespace)
+        .collect::<Vec<_>>(),
+      [
+        IntegerLiteral::from(42).into(),
+        IntegerLiteral::from(255).into(),
+        IntegerLiteral::from(10).into(),
+        IntegerLiteral::from(493).into(),
+        IntegerLiteral::from(1000).into(),
+        IntegerLiteral::new(0, Some("u8")).into(),
+        IntegerLiteral::new(255, Some("u8")).into(),
+        Token::EndOfFile,
+      ]
+    );
+  }
+
+  #[test]
+  fn invalid_integer_literals() {
+    let cases = [
+      ("x = 0b102;", LexErrorKind::InvalidDigit { digit: '2', radix: 2 }, 9, 1),
+      ("x = 0o8;", LexErrorKind::InvalidDigit { digit: '8', radix: 8 }, 7, 1),
+      ("x = 0x_;", LexErrorKind::MissingDigits, 5, 2),
+      ("x = 18446744073709551616;", LexErrorKind::IntegerTooLarge, 5, 20),
+      ("x = 12u128;", LexErrorKind::InvalidSuffix, 7, 4),
+      ("x = 0xFG;", LexErrorKind::InvalidSuffix, 8, 1),
+    ];
+
+    for (source, kind, column, width) in cases {
+      let mut tokenizer = Tokenizer::default();
+
+      assert_eq!(
+        tokenizer.tokenize_str(source).unwrap_err(),
+        LexError {
+          kind,
+          pos: CaretPos::from((1, column)),
+          width,
+        },
+        "{}",
+        source
+      );
+    }
+  }
+
+  #[test]
+  fn invalid_integer_literal_ends() {
+    let end = |source: &str| Tokenizer::default().tokenize_str(source).unwrap_err().end();
+
+    assert_eq!(end("x = 18446744073709551616;"), CaretPos::from((1, 25)));
+    assert_eq!(
+      end("x =\n  0x1_0000_0000_0000_0000_u64;"),
+      CaretPos::from((2, 27))
+    );
+    assert_eq!(end("x = 12u128;"), CaretPos::from((1, 11)));
+    assert_eq!(end("x = 7_i3 + 1;"), CaretPos::from((1, 9)));
+  }
+
+  #[test]
+  fn brackets() {
+    let actual = tokens("xs: [u8]; ys[i];");
+
+    assert_eq!(actual[3], Bracket::open().into());
+    assert_eq!(actual[5], Bracket::close().into());
+    assert_eq!(actual[9], Bracket::open().into());
+    assert_eq!(actual[11], Bracket::close().into());
+  }
+
+  #[test]
+  fn references() {
+    let actual = tokens("p: &u8; *p&&&q;");
+
+    assert_eq!(actual[3], operator(OperatorType::BitwiseAnd));
+    assert_eq!(
+      actual[7..11],
+      [
+        operator(OperatorType::Multiplication),
+        identifier("p"),
+        operator(OperatorType::LogicalAnd),
+        operator(OperatorType::BitwiseAnd),
+      ]
+    );
+  }
+
+  #[test]
+  fn closures() {
+    let actual = tokens("|x: u8| x + y;");
+
+    assert_eq!(
+      actual[..6],
+      [
+        operator(OperatorType::BitwiseOr),
+        identifier("x"),
+        operator(OperatorType::TypeSpecifier),
+        Token::Whitespace,
+        keyword("u8"),
+        operator(OperatorType::BitwiseOr),
+      ]
+    );
+    assert_eq!(tokens("|| main();")[0], operator(OperatorType::LogicalOr));
+  }
+
+  #[test]
+  fn methods() {
+    let actual =
+      tokens("impl Point {\n  norm(self): -> u8 {\n    return self.x;\n  };\n};\np.norm();\n");
+
+    assert_eq!(actual[0], keyword("impl"));
+    assert_eq!(actual[9], keyword("self"));
+    assert_eq!(
+      actual[34..38],
+      [
+        identifier("p"),
+        operator(OperatorType::FieldAccessor),
+        identifier("norm"),
+        Parenthesis::open().into(),
+      ]
+    );
+  }
+
+  #[test]
+  fn enums() {
+    let actual = tokens("enum Option<T> { Some(T), None };\nmatch x { Some(v) => v, None => y };");
+    let keywords: Vec<_> = actual
+      .iter()
+      .filter(|token| matches!(token, Token::Keyword(_)))
+      .cloned()
+      .collect();
+
+    assert_eq!(keywords, [keyword("enum"), keyword("match")]);
+    assert_eq!(
+      actual
+        .iter()
+        .filter(|token| **token == operator(OperatorType::MatchArm))
+        .count(),
+      2
+    );
+  }
+
+  #[test]
+  fn constants() {
+    let actual = tokens("const MAX: u8 = max;\nstatic ok: bool = a == b;\n");
+
+    assert_eq!(actual[0], keyword("const"));
+    assert_eq!(actual[7], operator(OperatorType::ValueAssignment));
+    assert_eq!(actual[12], keyword("static"));
+    assert_eq!(actual[23], operator(OperatorType::Equals));
+  }
+
+  #[test]
+  fn modules() {
+    let actual = tokens("pub mod shapes;\nuse shapes::Point;\n");
+
+    assert_eq!(
+      actual[..5],
+      [
+        keyword("pub"),
+        Token::Whitespace,
+        keyword("mod"),
+        Token::Whitespace,
+        identifier("shapes"),
+      ]
+    );
+    assert_eq!(
+      actual[7..12],
+      [
+        keyword("use"),
+        Token::Whitespace,
+        identifier("shapes"),
+        operator(OperatorType::ScopeAccessor),
+        identifier("Point"),
+      ]
+    );
+  }
+
+  #[test]
+  fn error_propagation() {
+    let actual = tokens("value: Result<u8, Error> = parse(text)?;");
+
+    assert_eq!(
+      actual[17..19],
+      [
+        operator(OperatorType::ErrorPropagation),
+        operator(OperatorType::StatementTerminator),
+      ]
+    );
+  }
+
+  #[test]
+  fn operator_traits() {
+    let actual = tokens("trait Add {\n};\nimpl Add for Vec2 {\n};\na - b / c % d;\n");
+
+    assert_eq!(actual[0], keyword("trait"));
+    assert_eq!(actual[13], keyword("for"));
+    assert_eq!(
+      actual
+        .iter()
+        .filter(|token| matches!(token, Token::Operator(_)))
+        .skip(2)
+        .take(3)
+        .cloned()
+        .collect::<Vec<_>>(),
+      [
+        operator(OperatorType::Subtraction),
+        operator(OperatorType::Division),
+        operator(OperatorType::Modulo),
+      ]
+    );
+  }
+
+  #[test]
+  fn defer() {
+    let actual = tokens("defer close(file);");
+
+    assert_eq!(
+      actual[..3],
+      [keyword("defer"), Token::Whitespace, identifier("close")]
+    );
+  }
+
+  #[test]
+  fn attributes() {
+    let actual = tokens("#[cfg(target = \"wasm32\")]\nstatic_assert(a == b);\n");
+
+    assert_eq!(
+      actual[..3],
+      [
+        operator(OperatorType::Attribute),
+        Bracket::open().into(),
+        identifier("cfg"),
+      ]
+    );
+    assert_eq!(actual[12], identifier("static_assert"));
+  }
+
+  #[test]
+  fn ranges() {
+    let actual = tokens("low..=high => a.b..c,");
+
+    assert_eq!(
+      actual,
+      [
+        identifier("low"),
+        operator(OperatorType::RangeInclusive),
+        identifier("high"),
+        Token::Whitespace,
+        operator(OperatorType::MatchArm),
+        Token::Whitespace,
+        identifier("a"),
+        operator(OperatorType::FieldAccessor),
+        identifier("b"),
+        operator(OperatorType::Range),
+        identifier("c"),
+        operator(OperatorType::CommaSeparator),
+        Token::EndOfFile,
+      ]
+    );
+  }
+
+  #[test]
+  fn stop_when_cancelled() {
+    let cancellation = CancellationToken::new();
+    cancellation.cancel();
+
+    let mut tokenizer = Tokenizer::with_cancellation(cancellation);
+    let actual = tokenizer.tokenize_str("main();\nx = 1;\n").unwrap();
+
+    assert_eq!(actual.len(), 5);
+  }
+
+  #[test]
+  fn unexpected_character() {
+    let mut tokenizer = Tokenizer::default();
+    let actual = tokenizer.tokenize_str("main();
+x = $y;
+").unwrap_err();
+
+    assert_eq!(
+      actual,
+      LexError {
+        kind: LexErrorKind::UnexpectedChar('$'),
+        pos: CaretPos::from((2, 5)),
+        width: 1,
+      }
+    );
+  }
 }
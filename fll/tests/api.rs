@@ -0,0 +1,140 @@
+//! Pins the signatures of everything in `fll::api`. If this stops compiling,
+//! the stable interface changed: either undo the change or make it on purpose
+//! and bump the major version.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use fll::api::*;
+
+type CancellableResult = Result<Result<Artifact, Diagnostics>, Cancelled>;
+type Tokens = Vec<(Token, Span)>;
+
+#[allow(dead_code)]
+struct NoopObserver;
+
+impl Observer for NoopObserver {
+  fn on_phase_start(&self, _phase: Phase, _path: &Path) {}
+
+  fn on_phase_finish(&self, _phase: Phase, _path: &Path, _elapsed: Duration) {}
+
+  fn on_file_lexed(&self, _path: &Path, _tokens: &[(Token, Span)]) {}
+
+  fn on_file_parsed(&self, _path: &Path, _module: &fll::ast::Module) {}
+
+  fn on_diagnostic(&self, _path: &Path, _diagnostic: &Diagnostic) {}
+}
+
+#[test]
+fn functions() {
+  let _: fn(SourceInput, CompileOptions) -> Result<Artifact, Diagnostics> = compile;
+
+  let _: fn(PathBuf) -> SourceInput = SourceInput::path;
+  let _: fn(String) -> SourceInput = SourceInput::text;
+  let _ = |input: SourceInput| match input {
+    SourceInput::Path(path) => path.into_os_string().into_string().unwrap(),
+    SourceInput::Text(text) => text,
+  };
+  let _: fn() -> CompileOptions = CompileOptions::default;
+  let _ = |options: CompileOptions| -> (Option<String>, u8, bool) {
+    (
+      options.target,
+      options.opt_level,
+      options.automatic_semicolons,
+    )
+  };
+
+  let _: fn(&Artifact) -> &[(Token, Span)] = Artifact::tokens;
+  let _: fn(Artifact) -> Vec<(Token, Span)> = Artifact::into_tokens;
+
+  let _: fn(String) -> Diagnostic = Diagnostic::error;
+  let _: fn(&Diagnostic) -> &str = Diagnostic::message;
+  let _: fn(Diagnostic, Range<CaretPos>) -> Diagnostic = Diagnostic::with_range;
+  let _: fn(&Diagnostic) -> Option<Range<CaretPos>> = Diagnostic::range;
+  let _: fn(&Diagnostics) -> std::slice::Iter<'_, Diagnostic> = Diagnostics::iter;
+  let _: fn(Diagnostic) -> Diagnostics = Diagnostics::from;
+  let _: fn(FadeError) -> Diagnostic = Diagnostic::from;
+
+  let _: fn() -> SessionBuilder = Session::builder;
+  let _: fn(&Session) -> Option<&str> = Session::target;
+  let _: fn(&Session) -> u8 = Session::opt_level;
+  let _: fn(&Session) -> bool = Session::automatic_semicolons;
+  let _: fn(&Session, &Path) -> std::io::Result<String> = Session::read_source;
+  let _: fn(&Session, SourceInput) -> Result<Tokens, Diagnostics> = Session::tokenize;
+  let _: fn(&Session, SourceInput) -> Result<Artifact, Diagnostics> = Session::compile;
+  let _: fn(&Session, SourceInput, &CancellationToken) -> CancellableResult =
+    Session::compile_cancellable;
+
+  let _: fn(SessionBuilder, String) -> SessionBuilder = SessionBuilder::target;
+  let _: fn(SessionBuilder, u8) -> SessionBuilder = SessionBuilder::opt_level;
+  let _: fn(SessionBuilder, PathBuf, String) -> SessionBuilder = SessionBuilder::file;
+  let _: fn(SessionBuilder, bool) -> SessionBuilder = SessionBuilder::treat_err_as_bug;
+  let _: fn(SessionBuilder, bool) -> SessionBuilder = SessionBuilder::automatic_semicolons;
+  let _: fn(SessionBuilder, Arc<dyn Observer>) -> SessionBuilder = SessionBuilder::observer;
+  let _: fn(SessionBuilder) -> Session = SessionBuilder::build;
+
+  let _: fn() -> CancellationToken = CancellationToken::new;
+  let _: fn(&CancellationToken) = CancellationToken::cancel;
+  let _: fn(&CancellationToken) -> bool = CancellationToken::is_cancelled;
+  let _: fn(&CancellationToken) -> Result<(), Cancelled> = CancellationToken::check;
+
+  let _: fn(&Phase) -> &'static str = Phase::name;
+  let _: fn(&CaretPos) -> usize = CaretPos::get_line;
+  let _: fn(&CaretPos) -> usize = CaretPos::get_column;
+  let _: fn(&LexError) -> CaretPos = LexError::end;
+}
+
+#[test]
+fn types() {
+  fn assert_send_sync<T: Send + Sync>() {}
+  fn assert_error<T: std::error::Error>() {}
+
+  assert_send_sync::<Session>();
+  assert_send_sync::<CancellationToken>();
+  assert_error::<Diagnostics>();
+  assert_error::<FadeError>();
+  assert_error::<LexError>();
+  assert_error::<ParseError>();
+  assert_error::<Cancelled>();
+
+  let _ = |err: FadeError| match err {
+    FadeError::Io { path, source } => (path, source.to_string()),
+    FadeError::Lex { path, source } => (path, source.to_string()),
+    FadeError::Parse { path, source } => (path, source.to_string()),
+    FadeError::Cancelled(cancelled) => (PathBuf::new(), cancelled.to_string()),
+    _ => (PathBuf::new(), String::new()),
+  };
+  let _ = |err: LexError| -> (LexErrorKind, CaretPos, usize) { (err.kind, err.pos, err.width) };
+  let _ = |kind: LexErrorKind| match kind {
+    LexErrorKind::UnexpectedChar(found) => Some(found),
+    LexErrorKind::UnterminatedString => None,
+    LexErrorKind::UnknownEscape(escape) => Some(escape),
+    _ => None,
+  };
+  let _ = |err: ParseError| -> (ParseErrorKind, Span) { (err.kind, err.span) };
+  let _ = |span: Span| -> (CaretPos, CaretPos, std::ops::Range<usize>) {
+    (span.start, span.end, span.byte_range)
+  };
+  let _ = |kind: ParseErrorKind| match kind {
+    ParseErrorKind::Unexpected { expected, found } => (expected, found),
+    ParseErrorKind::MisspelledKeyword { found, keyword } => (found, keyword),
+    _ => (String::new(), String::new()),
+  };
+  let _ = [Phase::Read, Phase::Lex, Phase::Parse];
+  let _ = |token: Token| match token {
+    Token::EndOfFile => "end of file",
+    Token::NewLine => "new line",
+    Token::Whitespace => "whitespace",
+    Token::Keyword(_) => "keyword",
+    Token::Identifier(_) => "identifier",
+    Token::StringLiteral(_) => "string literal",
+    Token::IntegerLiteral(_) => "integer literal",
+    Token::Parenthesis(_) => "parenthesis",
+    Token::Bracket(_) => "bracket",
+    Token::Brace(_) => "brace",
+    Token::Operator(_) => "operator",
+    _ => "other",
+  };
+}